@@ -0,0 +1,144 @@
+//! 地址类型与字符集
+
+use bitcoin::Network;
+
+pub const BECH32: &str = "qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+pub const BASE58: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// 地址类型
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Addr { Taproot, SegWit, Legacy, P2SH }
+
+/// 地址可用字符集
+pub fn charset(a: Addr) -> &'static str {
+    match a { Addr::Taproot | Addr::SegWit => BECH32, _ => BASE58 }
+}
+
+pub fn is_bech32(a: Addr) -> bool {
+    matches!(a, Addr::Taproot | Addr::SegWit)
+}
+
+pub fn base(a: Addr) -> u64 {
+    match a { Addr::Taproot | Addr::SegWit => 32, _ => 58 }
+}
+
+/// 是否为主网
+pub fn is_main(n: Network) -> bool { n == Network::Bitcoin }
+
+/// 地址固定前缀。测试网 Legacy 首字符可能是 m 或 n，这里的 "m/n" 只用于界面说明，
+/// 拼目标请用 `Target::full`
+pub fn pfx(a: Addr, n: Network) -> &'static str {
+    match (a, n) {
+        (Addr::Taproot, Network::Bitcoin) => "bc1p",
+        (Addr::Taproot, Network::Regtest) => "bcrt1p",
+        (Addr::Taproot, _) => "tb1p",
+        (Addr::SegWit, Network::Bitcoin) => "bc1q",
+        (Addr::SegWit, Network::Regtest) => "bcrt1q",
+        (Addr::SegWit, _) => "tb1q",
+        (Addr::Legacy, Network::Bitcoin) => "1",
+        (Addr::Legacy, _) => "m/n",
+        (Addr::P2SH, Network::Bitcoin) => "3",
+        (Addr::P2SH, _) => "2",
+    }
+}
+
+/// 地址末尾视为校验和的字符数，两种编码都按 6 位算。
+///
+/// Bech32 的校验和恰为末尾 6 个字符。Base58 的 4 字节校验和是载荷的低 32 位，与字符不对齐:
+/// 末尾 k 位是载荷除以 58^k 的余数，58^5 < 2^32，所以末尾 5 位基本由随机的校验和决定；
+/// 58^6 约为 2^35，倒数第 6 位仍有一部分受校验和影响。取 6 位宁可多排除一位，
+/// 也不让"不含校验和"的后缀 / 包含匹配落到由校验和决定的字符上
+pub fn checksum_len(_: Addr) -> usize { 6 }
+
+/// 前缀匹配时跳过的固定字符数（HRP + 分隔符 + 见证版本，或 Base58 版本首字符）
+pub fn pfx_len(a: Addr, n: Network) -> usize {
+    if is_bech32(a) { pfx(a, n).len() } else { 1 }
+}
+
+/// 助记词派生路径，测试网币种为 1'
+pub fn deriv(a: Addr, n: Network) -> &'static str {
+    match (a, is_main(n)) {
+        (Addr::Taproot, true) => "m/86'/0'/0'/0/0",
+        (Addr::Taproot, false) => "m/86'/1'/0'/0/0",
+        (Addr::SegWit, true) => "m/84'/0'/0'/0/0",
+        (Addr::SegWit, false) => "m/84'/1'/0'/0/0",
+        (_, true) => "m/44'/0'/0'/0/0",
+        (_, false) => "m/44'/1'/0'/0/0",
+    }
+}
+
+/// 匹配目标 `s` 的期望尝试次数；忽略大小写时每个字符按可接受的写法数折算
+pub fn exp(s: &str, a: Addr, nocase: bool) -> u64 {
+    if !nocase || is_bech32(a) { return base(a).saturating_pow(s.chars().count() as u32); }
+    let p: f64 = s.chars().map(|c| casings(c).len() as f64 / base(a) as f64).product();
+    (1.0 / p).round() as u64
+}
+
+/// 忽略大小写时字符 `c` 在 Base58 中的全部写法（`l` 没有大写，`I` / `O` 没有小写）
+pub fn casings(c: char) -> Vec<char> {
+    let mut v: Vec<char> = [c.to_ascii_lowercase(), c.to_ascii_uppercase()].into_iter()
+        .filter(|&x| BASE58.contains(x)).collect();
+    v.dedup();
+    v
+}
+
+/// 校验目标字符，返回规范化后的字符串；忽略大小写时 Base58 字符只需有一种写法可用
+pub fn validate(s: &str, a: Addr, nocase: bool) -> Option<String> {
+    let cs = charset(a);
+    if is_bech32(a) {
+        // Bech32: 只允许小写
+        let low = s.to_lowercase();
+        if low.chars().all(|c| cs.contains(c)) { Some(low) } else { None }
+    } else if nocase {
+        // 保留输入的写法用于显示，如 `lol` 实际匹配 `LoL` / `Lol` 等
+        if s.chars().all(|c| !casings(c).is_empty()) { Some(s.to_string()) } else { None }
+    } else {
+        // Base58: 保留大小写
+        if s.chars().all(|c| cs.contains(c)) { Some(s.to_string()) } else { None }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn casings_follow_base58() {
+        assert_eq!(casings('7'), vec!['7']);
+        assert_eq!(casings('a'), vec!['a', 'A']);
+        assert_eq!(casings('Z'), vec!['z', 'Z']);
+        // l 只有大写，I / O 只有小写，0 两种都没有
+        assert_eq!(casings('l'), vec!['L']);
+        assert_eq!(casings('L'), vec!['L']);
+        assert_eq!(casings('I'), vec!['i']);
+        assert_eq!(casings('O'), vec!['o']);
+        assert_eq!(casings('0'), Vec::<char>::new());
+    }
+
+    #[test]
+    fn nocase_difficulty() {
+        // 数字只有一种写法，难度不变
+        assert_eq!(exp("123", Addr::Legacy, true), exp("123", Addr::Legacy, false));
+        assert_eq!(exp("123", Addr::Legacy, true), 58u64.pow(3));
+        // 两种写法的字母每位除以 2
+        assert_eq!(exp("ab", Addr::Legacy, false), 58 * 58);
+        assert_eq!(exp("ab", Addr::Legacy, true), 29 * 29);
+        assert_eq!(exp("abc", Addr::P2SH, true), (58f64.powi(3) / 8.0).round() as u64);
+        // 只有一种写法的字母不打折
+        assert_eq!(exp("lIO", Addr::Legacy, true), 58u64.pow(3));
+        assert_eq!(exp("la", Addr::Legacy, true), 58 * 29);
+        // Bech32 没有大小写之分
+        assert_eq!(exp("ab", Addr::SegWit, true), 32 * 32);
+    }
+
+    #[test]
+    fn nocase_validation() {
+        // 忽略大小写时只要有一种写法可用，保留输入写法
+        assert_eq!(validate("lol", Addr::Legacy, true).as_deref(), Some("lol"));
+        assert_eq!(validate("lol", Addr::Legacy, false), None);
+        assert_eq!(validate("LoL", Addr::Legacy, false).as_deref(), Some("LoL"));
+        assert_eq!(validate("a0", Addr::Legacy, true), None);
+        assert_eq!(validate("123", Addr::P2SH, true).as_deref(), Some("123"));
+        assert_eq!(validate("QQ", Addr::SegWit, true).as_deref(), Some("qq"));
+    }
+}
@@ -0,0 +1,240 @@
+//! Base58 前缀与数值区间
+//!
+//! Base58Check 地址是 25 字节载荷 `版本 || hash160 || 校验和` 的 58 进制表示，
+//! 开头每个 `1` 对应一个前导零字节。固定前缀 + 固定位数时，载荷落在一个连续整数区间内，
+//! 于是前缀匹配可以换算成 hash160 的区间比较，无需每次编码。
+
+use crate::addr::BASE58;
+
+/// 256 位无符号整数（小端 64 位分组），只实现区间换算所需的运算
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct U256([u64; 4]);
+
+impl Ord for U256 {
+    fn cmp(&self, o: &Self) -> std::cmp::Ordering {
+        self.0.iter().rev().cmp(o.0.iter().rev())
+    }
+}
+
+impl PartialOrd for U256 {
+    fn partial_cmp(&self, o: &Self) -> Option<std::cmp::Ordering> { Some(self.cmp(o)) }
+}
+
+impl U256 {
+    pub const ZERO: U256 = U256([0; 4]);
+
+    pub fn from_u64(v: u64) -> U256 { U256([v, 0, 0, 0]) }
+
+    /// 2^bits
+    pub fn pow2(bits: u32) -> U256 {
+        let mut r = [0u64; 4];
+        r[(bits / 64) as usize] = 1 << (bits % 64);
+        U256(r)
+    }
+
+    pub fn mul_small(&self, m: u64) -> U256 {
+        let mut r = [0u64; 4];
+        let mut carry = 0u128;
+        for (i, limb) in r.iter_mut().enumerate() {
+            let v = self.0[i] as u128 * m as u128 + carry;
+            *limb = v as u64;
+            carry = v >> 64;
+        }
+        U256(r)
+    }
+
+    pub fn add(&self, o: &U256) -> U256 {
+        let mut r = [0u64; 4];
+        let mut carry = 0u128;
+        for (i, limb) in r.iter_mut().enumerate() {
+            let v = self.0[i] as u128 + o.0[i] as u128 + carry;
+            *limb = v as u64;
+            carry = v >> 64;
+        }
+        U256(r)
+    }
+
+    /// 饱和减法
+    pub fn sub(&self, o: &U256) -> U256 {
+        if self <= o { return U256::ZERO; }
+        let mut r = [0u64; 4];
+        let mut borrow = false;
+        for (i, limb) in r.iter_mut().enumerate() {
+            let (v, b1) = self.0[i].overflowing_sub(o.0[i]);
+            let (v, b2) = v.overflowing_sub(borrow as u64);
+            *limb = v;
+            borrow = b1 || b2;
+        }
+        U256(r)
+    }
+
+    pub fn shr(&self, bits: u32) -> U256 {
+        let (w, b) = ((bits / 64) as usize, bits % 64);
+        let mut r = [0u64; 4];
+        for (i, limb) in r.iter_mut().enumerate().take(4 - w) {
+            *limb = self.0[i + w] >> b;
+            if b > 0 && i + w + 1 < 4 { *limb |= self.0[i + w + 1] << (64 - b); }
+        }
+        U256(r)
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.0.iter().rev().fold(0.0, |acc, &l| acc * 18446744073709551616.0 + l as f64)
+    }
+
+    /// 低 160 位的大端字节
+    pub fn to_h160(self) -> [u8; 20] {
+        let mut b = [0u8; 32];
+        for i in 0..4 {
+            b[24 - i * 8..32 - i * 8].copy_from_slice(&self.0[i].to_be_bytes());
+        }
+        b[12..].try_into().unwrap()
+    }
+}
+
+/// hash160 闭区间
+pub type Range = ([u8; 20], [u8; 20]);
+
+/// 载荷 (版本 || hash160 || 校验和) 的整数区间 [a, b)，与版本区间取交集
+fn payload_ranges(full: &str, ver: u8) -> Vec<(U256, U256)> {
+    let z = full.chars().take_while(|&c| c == '1').count();
+    let rest = &full[z..];
+    // 版本 0 必然带一个前导 1，非零版本不可能有
+    if (ver == 0) != (z > 0) { return vec![]; }
+
+    // 25 个前导 1 已是全零载荷，之后不能再有字符
+    if z > 25 || (z == 25 && !rest.is_empty()) { return vec![]; }
+    let vlo = shl192(U256::from_u64(ver as u64));
+    let vhi = shl192(U256::from_u64(ver as u64 + 1));
+
+    // 前导零字节数恰为 z（rest 为空时至少为 z）
+    let zhi = if z == 0 { U256::pow2(200) } else { U256::pow2(8 * (25 - z as u32)) };
+    let zlo = if rest.is_empty() || z == 0 { U256::ZERO } else { U256::pow2(8 * (24 - z as u32)) };
+    let (lo, hi) = (vlo.max(zlo), vhi.min(zhi));
+    if lo >= hi { return vec![]; }
+
+    if rest.is_empty() { return vec![(lo, hi)]; }
+
+    let mut rv = U256::ZERO;
+    for c in rest.chars() {
+        let d = match BASE58.find(c) { Some(d) => d as u64, None => return vec![] };
+        rv = rv.mul_small(58).add(&U256::from_u64(d));
+    }
+
+    // 枚举编码位数 D：载荷在 [R·58^(D-|R|), (R+1)·58^(D-|R|))，位数每加一两端各乘 58
+    let mut out = vec![];
+    let (mut a, mut b) = (rv, rv.add(&U256::from_u64(1)));
+    for _ in rest.len()..=35 {
+        if a >= hi { break; }
+        let (x, y) = (a.max(lo), b.min(hi));
+        if x < y { out.push((x, y)); }
+        (a, b) = (a.mul_small(58), b.mul_small(58));
+    }
+    out
+}
+
+fn shl192(v: U256) -> U256 { U256([0, 0, 0, v.0[0]]) }
+
+/// 随机地址以 `full`（含版本首字符）开头的精确概率: 命中区间占该版本全部载荷的比例
+///
+/// 前导 `1` 表示零字节（每个约 1/256），开头字符越大、越靠近版本区间边界越少见，
+/// 与按 58 进制均匀估计可能相差很多倍。
+pub fn prefix_prob(full: &str, ver: u8) -> f64 {
+    let total = U256::pow2(192).to_f64();
+    payload_ranges(full, ver).iter().map(|(a, b)| b.sub(a).to_f64()).sum::<f64>() / total
+}
+
+/// 以 `head` 开头的地址下一位可能出现的字符
+pub fn next_chars(head: &str, ver: u8) -> String {
+    BASE58.chars().filter(|c| !payload_ranges(&format!("{}{}", head, c), ver).is_empty()).collect()
+}
+
+/// `full`（含版本首字符）作为前缀时，hash160 可能落入的闭区间
+///
+/// 区间边界处是否命中还取决于校验和，因此命中后仍需编码确认。
+pub fn prefix_ranges(full: &str, ver: u8) -> Vec<Range> {
+    let base = shl192(U256::from_u64(ver as u64));
+    payload_ranges(full, ver).into_iter().map(|(a, b)| {
+        let lo = a.sub(&base).shr(32);
+        let hi = b.sub(&base).sub(&U256::from_u64(1)).shr(32);
+        (lo.to_h160(), hi.to_h160())
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::RngCore;
+
+    /// 版本 + hash160 + 校验和组成的 25 字节载荷及其地址
+    fn payload(ver: u8, h: &[u8; 20]) -> (U256, String) {
+        let mut data = vec![ver];
+        data.extend_from_slice(h);
+        let addr = bitcoin::base58::encode_check(&data);
+        let bytes = bitcoin::base58::decode(&addr).unwrap();
+        let v = bytes.iter().fold(U256::ZERO, |acc, &b| acc.mul_small(256).add(&U256::from_u64(b as u64)));
+        (v, addr)
+    }
+
+    fn contains(rs: &[(U256, U256)], v: U256) -> bool { rs.iter().any(|(a, b)| *a <= v && v < *b) }
+
+    #[test]
+    fn random_payloads_fall_in_ranges() {
+        let mut rng = rand::thread_rng();
+        for ver in [0u8, 5, 0x6f, 0xc4] {
+            for _ in 0..200 {
+                let mut h = [0u8; 20];
+                rng.fill_bytes(&mut h);
+                // 偶尔带前导零字节，覆盖多个前导 1
+                let zeros = (rng.next_u32() % 4) as usize;
+                h[..zeros].fill(0);
+                let (v, addr) = payload(ver, &h);
+                for n in 1..=8 {
+                    let head = &addr[..n];
+                    assert!(contains(&payload_ranges(head, ver), v), "{} 不在 {} 的区间里", addr, head);
+                    assert!(prefix_ranges(head, ver).iter().any(|(lo, hi)| lo <= &h && &h <= hi), "{} hash160 不在 {} 的区间里", addr, head);
+                    assert!(prefix_prob(head, ver) > 0.0);
+                    // 换掉最后一位的前缀不应包含该载荷
+                    let other: String = head[..n - 1].chars().chain(BASE58.chars().find(|&c| !head.ends_with(c))).collect();
+                    assert!(!contains(&payload_ranges(&other, ver), v), "{} 不应在 {} 的区间里", addr, other);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn prob_splits_over_next_chars() {
+        for (head, ver) in [("1", 0u8), ("1A", 0), ("11", 0), ("3", 5), ("3J", 5), ("m", 0x6f), ("2N", 0xc4)] {
+            let p = prefix_prob(head, ver);
+            let sum: f64 = BASE58.chars().map(|c| prefix_prob(&format!("{}{}", head, c), ver)).sum();
+            assert!((sum - p).abs() <= p * 1e-9, "{} {} != {}", head, p, sum);
+        }
+        assert!((prefix_prob("1", 0) - 1.0).abs() < 1e-12);
+        assert!((prefix_prob("3", 5) - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn impossible_prefixes() {
+        // P2SH 载荷小于 6·2^192，3z 开头的数值都超出这个上限
+        assert!(payload_ranges("3z", 5).is_empty());
+        assert!(payload_ranges("3", 5).len() == 1);
+        // 非零版本没有前导 1，版本 0 必须有
+        assert!(payload_ranges("1", 5).is_empty());
+        assert!(payload_ranges("3", 0).is_empty());
+        // 载荷只有 25 字节，之后的 1 不可能出现（校验和不在区间换算之内，最后几个零字节交给编码确认）
+        assert!(!payload_ranges(&"1".repeat(21), 0).is_empty());
+        assert!(!payload_ranges(&"1".repeat(25), 0).is_empty());
+        assert!(payload_ranges(&format!("{}2", "1".repeat(25)), 0).is_empty());
+        assert!(payload_ranges(&"1".repeat(26), 0).is_empty());
+        assert!(next_chars(&"1".repeat(25), 0).is_empty());
+        // 测试网首字符只能是 m / n（P2PKH）或 2（P2SH）
+        assert_eq!(next_chars("", 0x6f), "mn");
+        assert_eq!(next_chars("", 0xc4), "2");
+        assert_eq!(next_chars("", 0), "1");
+        assert_eq!(next_chars("", 5), "3");
+        assert!(payload_ranges("o", 0x6f).is_empty());
+        assert!(payload_ranges("l", 0x6f).is_empty());
+        // n 开头只到 0x6f 区间上界之前
+        assert!(!next_chars("n", 0x6f).is_empty() && !next_chars("n", 0x6f).contains('z'));
+    }
+}
@@ -0,0 +1,670 @@
+//! 非交互命令行模式: `btc-vanity search [选项] <目标,...>`
+
+use btc_vanity::{dict, leet, list, score, Error, Addr, Collect, Found, KeyGen, Ledger, Limit, Match, Network, Odds, Out, Partial, RngMode, Searcher, Settings, Spec, Target};
+use serde_json::json;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// 退出码
+pub const EXIT_FOUND: i32 = 0;
+pub const EXIT_TIMEOUT: i32 = 1;
+pub const EXIT_INVALID: i32 = 2;
+pub const EXIT_ATTEMPTS: i32 = 3;
+pub const EXIT_PROBABILITY: i32 = 4;
+pub const EXIT_INTERRUPTED: i32 = 130;
+/// 找到结果之前 stdout 已被关闭，同 SIGPIPE 结束时 shell 给出的 128 + 13
+pub const EXIT_CLOSED: i32 = 141;
+
+const USAGE: &str = "\
+用法: btc-vanity search [选项] <目标,...>
+      btc-vanity search [选项] -F <目标文件>
+      btc-vanity search [选项] -W <词表文件>
+      btc-vanity search [选项] --min-score <分数>
+
+选项:
+  -t, --type <类型>      taproot | segwit | legacy | p2sh  (默认 taproot)
+      --network <网络>   mainnet | testnet | signet | regtest (默认 mainnet)
+  -m, --match <模式>     prefix | suffix | contains | pattern | both (默认 prefix)
+                         目标写成 前缀...后缀 时自动两端匹配，? 占位，如 mky...2024、ab??cd...
+                         pattern 为正则子集，匹配完整地址，如 '^bc1q[0-9]{4}' '(.)\\1{5}$'
+                         模式中的逗号不作分隔，多个模式请分开写
+  -i, --ignore-case      Base58 目标忽略大小写 (l 没有大写, I/O 没有小写)
+      --leet             目标含字符集以外的字符时换成形近字，展开全部写法一起搜索
+                         (如 Bech32 下 bob → 808, 806, 608, 606)
+      --no-checksum      后缀/包含匹配不含末尾 6 位校验和 (默认包含)
+  -F, --targets-file <路径>
+                         从文件读取目标，每行 `目标 [模式] [优先级]`，# 为注释；
+                         搜索中文件被修改时重新读取，换成新的目标接着搜
+  -W, --words <路径>     词典模式: 地址开头或数据部分出现词表中任一词即命中，
+                         结束时按词长与罕见度排序列出
+      --min-len <N>      词典模式的最短词长 (默认 4)
+      --min-score <分数> 美观评分: 连续相同字符、循环、回文、顺序等特征的总分达到该值即命中，
+                         可以不给目标；单项分数约等于同样难得的靓号位数，如 5
+  -o, --output <格式>    mnemonic | wif | both              (默认 mnemonic)
+  -r, --rng <随机源>     secure | fast                      (默认 secure)
+  -k, --keygen <方式>    random | incremental  纯私钥模式的私钥生成方式 (默认 random；
+                         incremental 每批一个随机起点依次 +1，速度快很多)
+  -j, --threads <N>      线程数量 (默认 CPU 核数)
+  -b, --batch <N>        批处理量 (默认 512)
+  -n, --count <N>        收集数量: 数字 | each (每个目标一个) | all (不限，直到超时或中断)
+      --timeout <时长>   运行超过该时长后停止，如 90、30m、2h、1d (不带单位为秒)
+      --max-attempts <N> 尝试 N 次后停止
+      --max-prob <百分比>
+                         命中任一目标的累计概率达到该值后停止，如 90 或 99.9%
+                         达到任一上限时输出未找到的汇总 (尝试次数、速度、最接近的地址)
+      --near <N>         结束时列出匹配位数最多的 N 个近似命中 (只有地址)
+      --keep-near        同时输出这些近似命中的密钥 (未指定 --near 时取 10 个)
+  -f, --format <格式>    text | json  (json 为逐行 NDJSON 事件)
+      --interval <秒>    json 模式下进度事件间隔 (默认 1)
+      --no-ledger        不读写累计工作量账本 (默认 ~/.btc-vanity/ledger.json，
+                         可用环境变量 BTC_VANITY_LEDGER 指定)
+  -h, --help             显示帮助
+
+退出码:
+  0 找到 (至少一个)  1 超时  2 参数或目标无效  3 达到尝试次数上限  4 达到概率上限  130 被中断
+  141 找到之前输出已被关闭 (如接在 | head 后面)
+
+不带参数运行进入交互界面。";
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format { Text, Json }
+
+struct Args {
+    settings: Settings,
+    targets: Vec<String>,
+    files: Vec<String>,
+    /// 词表文件及最短词长
+    words: Vec<String>,
+    min_len: usize,
+    format: Format,
+    interval: Duration,
+    ledger: bool,
+    /// 展开形近字写法
+    leet: bool,
+    /// 结束时列出的近似命中数，及是否输出其密钥
+    near: usize,
+    keep_near: bool,
+}
+
+fn addr_id(a: Addr) -> &'static str {
+    match a { Addr::Taproot => "taproot", Addr::SegWit => "segwit", Addr::Legacy => "legacy", Addr::P2SH => "p2sh" }
+}
+
+fn match_id(m: Match) -> &'static str {
+    match m { Match::Prefix => "prefix", Match::Suffix => "suffix", Match::Contains => "contains", Match::Pattern => "pattern", Match::Both => "both" }
+}
+
+fn out_id(o: Out) -> &'static str {
+    match o { Out::Mnemonic => "mnemonic", Out::Wif => "wif", Out::Both => "both" }
+}
+
+fn parse_addr(s: &str) -> Option<Addr> {
+    match s.to_lowercase().as_str() {
+        "taproot" | "p2tr" | "bc1p" => Some(Addr::Taproot),
+        "segwit" | "p2wpkh" | "bc1q" => Some(Addr::SegWit),
+        "legacy" | "p2pkh" => Some(Addr::Legacy),
+        "p2sh" | "p2sh-p2wpkh" => Some(Addr::P2SH),
+        _ => None,
+    }
+}
+
+fn parse_net(s: &str) -> Option<Network> {
+    match s.to_lowercase().as_str() {
+        "mainnet" | "bitcoin" | "main" => Some(Network::Bitcoin),
+        "testnet" | "test" => Some(Network::Testnet),
+        "signet" => Some(Network::Signet),
+        "regtest" => Some(Network::Regtest),
+        _ => None,
+    }
+}
+
+fn parse_out(s: &str) -> Option<Out> {
+    match s.to_lowercase().as_str() {
+        "mnemonic" => Some(Out::Mnemonic),
+        "wif" => Some(Out::Wif),
+        "both" => Some(Out::Both),
+        _ => None,
+    }
+}
+
+fn parse_rng(s: &str) -> Option<RngMode> {
+    match s.to_lowercase().as_str() {
+        "secure" => Some(RngMode::Secure),
+        "fast" => Some(RngMode::Fast),
+        _ => None,
+    }
+}
+
+/// 时长: 秒数，或带 s / m / h / d 单位
+pub fn parse_time(s: &str) -> Option<Duration> {
+    let s = s.trim();
+    let (num, unit) = match s.char_indices().last()? {
+        (i, c) if c.is_ascii_alphabetic() => (&s[..i], c.to_ascii_lowercase()),
+        _ => (s, 's'),
+    };
+    let mul = match unit { 's' => 1.0, 'm' => 60.0, 'h' => 3600.0, 'd' => 86400.0, _ => return None };
+    let secs = num.trim().parse::<f64>().ok().filter(|&n| n > 0.0)? * mul;
+    secs.is_finite().then(|| Duration::from_secs_f64(secs))
+}
+
+/// 百分比（可带 `%`），返回 0-1 之间的概率
+pub fn parse_pct(s: &str) -> Option<f64> {
+    let p: f64 = s.trim().trim_end_matches('%').trim().parse().ok()?;
+    (p > 0.0 && p < 100.0).then_some(p / 100.0)
+}
+
+/// 停止原因在 json 事件中的名称
+fn limit_id(l: Limit) -> &'static str {
+    match l { Limit::Time => "timeout", Limit::Attempts => "attempts", Limit::Probability => "probability" }
+}
+
+fn limit_exit(l: Limit) -> i32 {
+    match l { Limit::Time => EXIT_TIMEOUT, Limit::Attempts => EXIT_ATTEMPTS, Limit::Probability => EXIT_PROBABILITY }
+}
+
+fn parse_args(args: &[String]) -> Result<Args, String> {
+    let mut a = Args {
+        settings: Settings::default(),
+        targets: vec![],
+        files: vec![],
+        words: vec![],
+        min_len: dict::MIN_LEN,
+        format: Format::Text,
+        interval: Duration::from_secs(1),
+        ledger: true,
+        leet: false,
+        near: 0,
+        keep_near: false,
+    };
+    let mut it = args.iter();
+
+    while let Some(arg) = it.next() {
+        // 支持 --flag=value 写法
+        let (flag, inline) = match arg.split_once('=') {
+            Some((f, v)) if f.starts_with("--") => (f, Some(v.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let mut val = |name: &str| -> Result<String, String> {
+            inline.clone().or_else(|| it.next().cloned()).ok_or_else(|| format!("{} 缺少参数值", name))
+        };
+        let bad = |name: &str, v: &str| format!("{} 的值无效: '{}'", name, v);
+
+        match flag {
+            "-t" | "--type" => {
+                let v = val(flag)?;
+                a.settings.addr_type = parse_addr(&v).ok_or_else(|| bad(flag, &v))?;
+            }
+            "--network" => {
+                let v = val(flag)?;
+                a.settings.network = parse_net(&v).ok_or_else(|| bad(flag, &v))?;
+            }
+            "-m" | "--match" => {
+                let v = val(flag)?;
+                a.settings.match_mode = Match::parse(&v).ok_or_else(|| bad(flag, &v))?;
+            }
+            "-i" | "--ignore-case" => a.settings.ignore_case = true,
+            "--leet" => a.leet = true,
+            "--no-checksum" => a.settings.checksum = false,
+            "-F" | "--targets-file" => a.files.push(val(flag)?),
+            "-W" | "--words" => a.words.push(val(flag)?),
+            "--min-len" => {
+                let v = val(flag)?;
+                a.min_len = v.parse().ok().filter(|&n| n >= 1).ok_or_else(|| bad(flag, &v))?;
+            }
+            "-o" | "--output" => {
+                let v = val(flag)?;
+                a.settings.output = parse_out(&v).ok_or_else(|| bad(flag, &v))?;
+            }
+            "-r" | "--rng" => {
+                let v = val(flag)?;
+                a.settings.rng_mode = parse_rng(&v).ok_or_else(|| bad(flag, &v))?;
+            }
+            "-k" | "--keygen" => {
+                let v = val(flag)?;
+                a.settings.keygen = match v.to_lowercase().as_str() {
+                    "incremental" | "inc" => KeyGen::Incremental,
+                    "random" => KeyGen::Random,
+                    _ => return Err(bad(flag, &v)),
+                };
+            }
+            "-j" | "--threads" => {
+                let v = val(flag)?;
+                a.settings.threads = v.parse().ok().filter(|&n| n >= 1).ok_or_else(|| bad(flag, &v))?;
+            }
+            "-b" | "--batch" => {
+                let v = val(flag)?;
+                a.settings.batch_size = v.parse().ok().filter(|&n| n >= 1).ok_or_else(|| bad(flag, &v))?;
+            }
+            "-n" | "--count" => {
+                let v = val(flag)?;
+                a.settings.collect = match v.to_lowercase().as_str() {
+                    "each" | "per-target" => Collect::PerTarget,
+                    "all" | "unlimited" => Collect::Unlimited,
+                    n => Collect::Total(n.parse().ok().filter(|&n| n >= 1).ok_or_else(|| bad(flag, &v))?),
+                };
+            }
+            "--timeout" => {
+                let v = val(flag)?;
+                a.settings.max_time = Some(parse_time(&v).ok_or_else(|| bad(flag, &v))?);
+            }
+            "--max-attempts" => {
+                let v = val(flag)?;
+                a.settings.max_attempts = Some(v.replace(['_', ','], "").parse().ok().filter(|&n| n >= 1).ok_or_else(|| bad(flag, &v))?);
+            }
+            "--max-prob" => {
+                let v = val(flag)?;
+                a.settings.max_prob = Some(parse_pct(&v).ok_or_else(|| bad(flag, &v))?);
+            }
+            "-f" | "--format" => {
+                let v = val(flag)?;
+                a.format = match v.to_lowercase().as_str() {
+                    "text" => Format::Text,
+                    "json" | "ndjson" => Format::Json,
+                    _ => return Err(bad(flag, &v)),
+                };
+            }
+            "--interval" => {
+                let v = val(flag)?;
+                let secs: f64 = v.parse().ok().filter(|&s: &f64| s > 0.0).ok_or_else(|| bad(flag, &v))?;
+                a.interval = Duration::from_secs_f64(secs);
+            }
+            "--no-ledger" => a.ledger = false,
+            "--min-score" => {
+                let v = val(flag)?;
+                a.settings.min_score = Some(v.parse().ok().filter(|&n: &f64| n > 0.0 && n.is_finite()).ok_or_else(|| bad(flag, &v))?);
+            }
+            "--near" => {
+                let v = val(flag)?;
+                a.near = v.parse().ok().filter(|&n| n >= 1).ok_or_else(|| bad(flag, &v))?;
+            }
+            "--keep-near" => a.keep_near = true,
+            f if f.starts_with('-') && f.len() > 1 => return Err(format!("未知选项: {}", f)),
+            _ => a.targets.push(arg.clone()),
+        }
+    }
+    if a.keep_near && a.near == 0 { a.near = 10; }
+    // 模式可能含逗号（如 {2,4}），匹配模式确定后再拆分
+    if a.settings.match_mode != Match::Pattern {
+        a.targets = a.targets.iter()
+            .flat_map(|t| t.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()).map(String::from))
+            .collect();
+    }
+    Ok(a)
+}
+
+/// stdout 已被关闭（如接在 `| head -1` 后面），搜索随即停止
+static CLOSED: AtomicBool = AtomicBool::new(false);
+
+/// 写到 stdout。`println!` 遇到写入失败会 panic，在 `panic = "abort"` 下直接以 134 退出，
+/// 这里改为记下管道已关闭，由主循环正常收尾
+fn out(s: &str) {
+    if let Err(e) = writeln!(io::stdout().lock(), "{}", s) {
+        if e.kind() != io::ErrorKind::BrokenPipe { eprintln!("错误: 无法写入标准输出: {}", e); }
+        CLOSED.store(true, Ordering::Relaxed);
+    }
+}
+
+/// 输出一行 JSON 事件
+fn emit(v: serde_json::Value) {
+    out(&v.to_string());
+}
+
+fn found_json(r: &Found, tot: u64, dur: Duration) -> serde_json::Value {
+    json!({
+        "event": "found",
+        "address": r.addr,
+        "target": r.target,
+        "path": r.path,
+        "mnemonic": r.mnemonic,
+        "wif": r.wif,
+        "pubkey": r.pubkey,
+        "script_pubkey": r.script_pubkey,
+        "attempts": tot,
+        "elapsed": dur.as_secs_f64(),
+        "score": r.score.as_ref().map(|s| s.total),
+        "reasons": r.score.as_ref().map(|s| s.explain()),
+    })
+}
+
+fn print_found(r: &Found) {
+    let mut s = format!("address: {}", r.addr);
+    if let Some(ref m) = r.mnemonic { s += &format!("\nmnemonic: {}", m); }
+    if let Some(ref p) = r.path { s += &format!("\npath: {}", p); }
+    if let Some(ref w) = r.wif { s += &format!("\nwif: {}", w); }
+    s += &format!("\npubkey: {}\ntarget: {}", r.pubkey, r.target);
+    if let Some(ref sc) = r.score {
+        s += &format!("\nscore: {:.1}", sc.total);
+        for e in sc.explain() { s += &format!("\n  {}", e); }
+    }
+    out(&s);
+}
+
+/// 运行 `search` 子命令，返回进程退出码
+pub fn search(args: &[String]) -> i32 {
+    if args.iter().any(|a| a == "-h" || a == "--help") {
+        out(USAGE);
+        return EXIT_FOUND;
+    }
+
+    let a = match parse_args(args) {
+        Ok(a) => a,
+        Err(e) => {
+            eprintln!("错误: {}\n\n{}", e, USAGE);
+            return EXIT_INVALID;
+        }
+    };
+    let json = a.format == Format::Json;
+    let (addr, nocase) = (a.settings.addr_type, a.settings.ignore_case);
+    let literal = a.settings.match_mode != Match::Pattern;
+
+    // 有无效字符的目标换成全部形近写法；找不到替换的保留原样，之后照常报错
+    let mut variants = serde_json::Map::new();
+    let targets: Vec<String> = a.targets.iter().flat_map(|t| {
+        let vs = (a.leet && literal && leet::needed(t, addr, nocase)).then(|| leet::variants(t, addr, nocase)).flatten();
+        match vs {
+            Some(vs) => {
+                if !json { eprintln!("{} → {} ({} 种写法)", t, vs.join(", "), vs.len()); }
+                variants.insert(t.clone(), json!(vs));
+                vs
+            }
+            None => vec![t.clone()],
+        }
+    }).collect();
+
+    // 目标文件被修改时，连同命令行目标和词表重新组成全部目标
+    let mut fixed: Vec<Spec> = if a.files.is_empty() { vec![] } else { targets.iter().map(Spec::new).collect() };
+    let mut watches: Vec<list::Watch> = a.files.iter().map(list::Watch::new).collect();
+    let mut searcher = Searcher::with_settings(a.settings.clone()).targets(targets);
+    for path in &a.files {
+        let l = match list::load(path, &a.settings) {
+            Ok(l) => l,
+            Err(e) => {
+                if json { emit(json!({ "event": "error", "message": format!("{}: {}", path, e) })); }
+                eprintln!("错误: 无法读取 {}: {}", path, e);
+                return EXIT_INVALID;
+            }
+        };
+        // 无效行跳过并提示，与交互界面一致
+        for (n, e) in l.errors.iter().take(10) { eprintln!("{}:{}: {}，已跳过", path, n, e); }
+        if l.errors.len() > 10 { eprintln!("{}: 另有 {} 行无效", path, l.errors.len() - 10); }
+        if !json {
+            eprintln!("{}: 读取 {} 个目标 (无效 {} 行, 重复 {} 行)", path, l.specs.len(), l.errors.len(), l.dups);
+        }
+        searcher = searcher.specs(l.specs);
+    }
+    for path in &a.words {
+        let w = match dict::load(path, a.min_len, &a.settings) {
+            Ok(w) => w,
+            Err(e) => {
+                if json { emit(json!({ "event": "error", "message": format!("{}: {}", path, e) })); }
+                eprintln!("错误: 无法读取 {}: {}", path, e);
+                return EXIT_INVALID;
+            }
+        };
+        if !json {
+            eprintln!("{}: 可用 {} 个词 (过短 {}, 不符合字符集 {}, 重复 {})", path, w.words.len(), w.short, w.invalid, w.dups);
+        }
+        if !a.files.is_empty() { fixed.extend(w.specs()); }
+        searcher = searcher.specs(w.specs());
+    }
+    // 概率上限从账本里的累计量接着算，需要先知道目标集合
+    if a.ledger && a.settings.max_prob.is_some() {
+        if let Ok(ts) = searcher.resolve() {
+            searcher = searcher.prior(Ledger::open(&ts, &a.settings).prior.since);
+        }
+    }
+
+    let handle = match searcher.spawn() {
+        Ok(h) => h,
+        Err(e) => {
+            if json { emit(json!({ "event": "error", "message": e.to_string() })); }
+            eprintln!("错误: {}", e);
+            if let Error::InvalidTarget(t) = &e {
+                if let Some((v, swaps)) = leet::suggest(t, addr, nocase).filter(|_| literal) {
+                    eprintln!("提示: 可改为 {} ({})，或加 --leet 展开全部形近写法", v, swaps.join(", "));
+                }
+            }
+            return EXIT_INVALID;
+        }
+    };
+
+    let s = handle.settings();
+    // 合并命中率，用于进度事件中的累计概率（仅 json 模式需要）
+    let odds_of = |ts: &[Target]| Odds::new(if json {
+        ts.iter().map(|t| t.expected(s)).chain(s.min_score.map(|m| score::expected(m, s))).collect()
+    } else { vec![] });
+    let targets = handle.targets();
+    let mut odds = odds_of(&targets);
+    let mut ledger = a.ledger.then(|| Ledger::open(&targets, s));
+    if json {
+        // 目标很多时只列出前面一部分，总数见 target_count
+        let total = targets.len();
+        let listed: Vec<_> = targets.iter().take(1000).map(|t| json!({
+            "target": t.raw,
+            "full": t.full(s),
+            "match": match_id(t.mode),
+            "priority": t.priority,
+            "expected": t.expected(s),
+        })).collect();
+        emit(json!({
+            "event": "start",
+            "type": addr_id(s.addr_type),
+            "network": s.network.to_string(),
+            "match": match_id(s.match_mode),
+            "ignore_case": s.ignore_case,
+            "checksum": s.checksum,
+            "output": out_id(s.output),
+            "threads": s.threads,
+            "count": match s.collect {
+                Collect::Total(n) => json!(n),
+                Collect::PerTarget => json!("each"),
+                Collect::Unlimited => json!("all"),
+            },
+            "target_count": total,
+            "min_score": s.min_score,
+            "expected": odds.expected().round() as u64,
+            // 之前搜索同一组目标的累计量
+            "ledger": ledger.as_ref().map(|l| json!({
+                "attempts": l.prior.attempts,
+                "elapsed": l.prior.secs,
+                "runs": l.prior.runs,
+                "found": l.prior.found,
+                "since": l.prior.since,
+            })),
+            "targets": listed,
+            // 展开了形近写法的原目标
+            "variants": variants,
+        }));
+    }
+
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let flag = interrupted.clone();
+    let _ = ctrlc::set_handler(move || flag.store(true, Ordering::Relaxed));
+
+    let (mut last, mut last_t) = (0u64, Instant::now());
+    let mut n = 0u64;
+    // 本次最后一次命中时的尝试次数，及最近一次换目标时的尝试次数
+    let mut hit = None;
+    let mut from = 0u64;
+    let mut saved = Instant::now();
+    // 词典模式结束时要排序，留下全部结果
+    let mut all: Vec<Found> = vec![];
+    let show = |r: &Found, n: u64| {
+        if json {
+            emit(found_json(r, handle.attempts(), handle.elapsed()));
+        } else {
+            if n > 1 { out(""); }
+            print_found(r);
+        }
+    };
+
+    // None: 收集完成; Some(code): 超时或中断
+    let stopped = loop {
+        match handle.recv_timeout(Duration::from_millis(200)) {
+            Ok(r) => { n += 1; hit = Some(handle.attempts()); show(&r, n); all.push(r); }
+            // 收集完成，或达到上限
+            Err(RecvTimeoutError::Disconnected) => break handle.limit().map(limit_exit),
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+        if interrupted.load(Ordering::Relaxed) { break Some(EXIT_INTERRUPTED); }
+        // 下游不再读取输出，停止搜索
+        if CLOSED.load(Ordering::Relaxed) { break Some(EXIT_CLOSED); }
+        if watches.iter_mut().fold(false, |c, w| w.changed() | c) {
+            // 有文件读不了时保留原来的目标
+            let loaded: Result<Vec<_>, _> = a.files.iter().map(|p| list::load(p, s).map_err(|e| format!("{}: {}", p, e))).collect();
+            let (cur, secs) = (handle.attempts(), handle.elapsed().as_secs_f64());
+            let switched = loaded.and_then(|ls| {
+                for (path, l) in a.files.iter().zip(&ls).filter(|(_, l)| !l.errors.is_empty()) {
+                    eprintln!("{}: 跳过无效 {} 行", path, l.errors.len());
+                }
+                let specs = fixed.iter().cloned().chain(ls.into_iter().flat_map(|l| l.specs));
+                let ts = Searcher::with_settings(s.clone()).specs(specs).resolve().map_err(|e| e.to_string())?;
+                // 旧目标的计数记到换目标为止，新目标从现在开始记
+                let l = a.ledger.then(|| Ledger::open(&ts, s).starting_at(cur, secs, n));
+                handle.set_targets(ts.iter().cloned().map(Spec::from), l.as_ref().map_or(0, |l| l.prior.since)).map_err(|e| e.to_string())?;
+                Ok((ts, l))
+            });
+            match switched {
+                Ok((ts, l)) => {
+                    if let Some(old) = ledger.as_mut() { old.record(cur, secs, n, hit).ok(); }
+                    ledger = l;
+                    odds = odds_of(&ts);
+                    from = cur;
+                    if json {
+                        emit(json!({
+                            "event": "reload",
+                            "attempts": cur,
+                            "target_count": ts.len(),
+                            "expected": odds.expected().round() as u64,
+                        }));
+                    } else {
+                        eprintln!("目标已更新: {} 个", ts.len());
+                    }
+                }
+                Err(e) => eprintln!("警告: 目标未更新: {}", e),
+            }
+        }
+        if let Some(l) = ledger.as_mut().filter(|_| saved.elapsed() >= Duration::from_secs(10)) {
+            l.record(handle.attempts(), handle.elapsed().as_secs_f64(), n, hit).ok();
+            saved = Instant::now();
+        }
+
+        if json && last_t.elapsed() >= a.interval {
+            let cur = handle.attempts();
+            let spd = (cur - last) as f64 / last_t.elapsed().as_secs_f64();
+            (last, last_t) = (cur, Instant::now());
+            emit(json!({
+                "event": "progress",
+                "attempts": cur,
+                "probability": odds.cumulative(ledger.as_ref().map_or(cur - hit.unwrap_or(0).max(from),
+                    |l| l.lifetime(cur, 0.0, n, hit).since)),
+                "speed": spd.round() as u64,
+                "elapsed": handle.elapsed().as_secs_f64(),
+            }));
+        }
+    };
+
+    // 停止后取出线程退出前已发出的结果
+    handle.stop();
+    while let Some(r) = handle.recv() { n += 1; hit = Some(handle.attempts()); show(&r, n); all.push(r); }
+    let tot = handle.attempts();
+    let dur = handle.elapsed();
+    if let Some(l) = ledger.as_mut() {
+        if let Err(e) = l.record(tot, dur.as_secs_f64(), n, hit) {
+            eprintln!("警告: 无法写入账本 {}: {}", l.path().display(), e);
+        }
+    }
+
+    let limit = handle.limit();
+    let why = match (stopped, limit) {
+        (None, _) => "done",
+        (Some(EXIT_CLOSED), _) => "closed",
+        (Some(c), Some(l)) if c == limit_exit(l) => limit_id(l),
+        _ => "interrupted",
+    };
+    let spd = (tot as f64 / dur.as_secs_f64().max(1e-9)).round() as u64;
+    // 最接近的地址只给出地址和匹配位数，不输出密钥
+    let best = handle.best();
+    let near = handle.board(a.near);
+    let ranked = if a.words.is_empty() { vec![] } else { dict::rank(&all, s) };
+    let near_json = |p: &Partial| json!({ "address": p.found.addr, "target": p.found.target, "matched": p.matched, "length": p.len });
+    if a.keep_near {
+        for p in &near {
+            if json {
+                let mut v = found_json(&p.found, tot, dur);
+                v["event"] = json!("near");
+                v["matched"] = json!(p.matched);
+                v["length"] = json!(p.len);
+                emit(v);
+            } else {
+                out("");
+                print_found(&p.found);
+                out(&format!("near: {}/{}", p.matched, p.len));
+            }
+        }
+    }
+    if json {
+        emit(json!({
+            "event": "end",
+            "reason": why,
+            // 达到的上限，没有达到时为 null（收集完成或中断时也可能同时达到）
+            "limit": limit.map(limit_id),
+            "found": n,
+            "attempts": tot,
+            "speed": spd,
+            "elapsed": dur.as_secs_f64(),
+            "best": best.as_ref().map(near_json),
+            "near": near.iter().map(near_json).collect::<Vec<_>>(),
+            "ranked": ranked.iter().map(|(i, sc)| json!({
+                "address": all[*i].addr,
+                "word": sc.word,
+                "length": sc.len,
+                "position": sc.at,
+                "prefix": sc.lead,
+                "rarity": sc.rarity.round() as u64,
+            })).collect::<Vec<_>>(),
+        }));
+    }
+    if n == 0 && stopped != Some(EXIT_CLOSED) {
+        let why = match limit {
+            Some(Limit::Time) => "超时",
+            Some(Limit::Attempts) => "达到尝试次数上限",
+            Some(Limit::Probability) => "达到概率上限",
+            None => "已中断",
+        };
+        eprintln!("{}: 未找到", why);
+        if let Some(b) = &best {
+            eprintln!("最接近: {} (目标 {} 匹配 {}/{} 位)", b.found.addr, b.found.target, b.matched, b.len);
+        }
+    }
+    if !json && !near.is_empty() {
+        eprintln!("近似命中:");
+        for p in &near { eprintln!("  {}  {}/{} {}", p.found.addr, p.matched, p.len, p.found.target); }
+    }
+    if !json && !ranked.is_empty() {
+        eprintln!("按词长与罕见度排序:");
+        for (i, sc) in &ranked {
+            let at = if sc.lead { "前缀".to_string() } else { format!("第 {} 位", sc.at + 1) };
+            eprintln!("  {}  {} ({} 位, {}, 罕见度 1/{})", all[*i].addr, sc.word, sc.len, at, crate::fmt_num(sc.rarity.round() as u64));
+        }
+    }
+    // 评分命中按分数从高到低列出
+    let mut scored: Vec<&Found> = all.iter().filter(|r| r.score.is_some()).collect();
+    if !json && scored.len() > 1 {
+        scored.sort_by(|x, y| y.score.as_ref().unwrap().total.total_cmp(&x.score.as_ref().unwrap().total));
+        eprintln!("按分数排序:");
+        for r in &scored { eprintln!("  {}  {:.1}", r.addr, r.score.as_ref().unwrap().total); }
+    }
+    if !json { eprintln!("找到 {} 个, 尝试 {} 次, 速度 {}/s, 耗时 {:.2?}", n, crate::fmt_num(tot), crate::fmt_num(spd), dur); }
+    if let Some(l) = ledger.as_ref().filter(|l| !json && l.resumed()) {
+        let w = l.lifetime(tot, dur.as_secs_f64(), n, hit);
+        eprintln!("累计: {} 次搜索, 尝试 {} 次, 耗时 {}, 找到 {} 个", w.runs, crate::fmt_num(w.attempts), crate::fmt_time(w.secs as u64), w.found);
+    }
+
+    let code = if n > 0 { EXIT_FOUND } else { stopped.unwrap_or(EXIT_INTERRUPTED) };
+    handle.join();
+    code
+}
@@ -0,0 +1,323 @@
+//! 搜索配置
+
+use crate::addr::{exp, is_bech32, is_main, pfx, validate, Addr};
+use crate::matcher::{lead, prefix_exact, prefix_why};
+use crate::pattern::Pattern;
+use crate::Error;
+use bitcoin::Network;
+use std::time::Duration;
+
+/// 匹配模式（`Pattern` 为正则表达式子集，见 [`crate::pattern`]；`Both` 为 `前缀...后缀` 同时匹配两端）
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Match { Prefix, Suffix, Contains, Pattern, Both }
+
+impl Match {
+    pub fn parse(s: &str) -> Option<Match> {
+        match s.to_lowercase().as_str() {
+            "prefix" | "前缀" => Some(Match::Prefix),
+            "suffix" | "后缀" => Some(Match::Suffix),
+            "contains" | "包含" => Some(Match::Contains),
+            "pattern" | "regex" | "模式" | "正则" => Some(Match::Pattern),
+            "both" | "ends" | "两端" => Some(Match::Both),
+            _ => None,
+        }
+    }
+}
+
+/// 输出格式
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Out { Mnemonic, Wif, Both }
+
+/// 随机源
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RngMode { Secure, Fast }
+
+/// 私钥生成方式（仅纯私钥模式生效）
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum KeyGen {
+    /// 每次尝试独立随机私钥
+    Random,
+    /// 每批一个随机起点，依次 +1 并用批量点加计算公钥
+    Incremental,
+}
+
+/// 收集模式: 去重后达到数量即停止
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Collect {
+    /// 共 N 个结果
+    Total(u64),
+    /// 每个目标至少一个
+    PerTarget,
+    /// 不限数量，直到手动停止
+    Unlimited,
+}
+
+/// 停止条件: 达到时间、尝试次数或累计命中概率上限
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Limit { Time, Attempts, Probability }
+
+/// 目标描述: 原始文本，可单独指定匹配模式（否则用全局设置）和优先级
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Spec { pub text: String, pub mode: Option<Match>, pub priority: u32 }
+
+impl Spec {
+    pub fn new(s: impl Into<String>) -> Spec {
+        Spec { text: s.into(), mode: None, priority: 0 }
+    }
+
+    /// 解析一行 `目标 [prefix|suffix|contains|pattern|both] [优先级]`；空行和 `#` 注释返回 None
+    pub fn parse(line: &str) -> Result<Option<Spec>, String> {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut it = line.split_whitespace();
+        let Some(text) = it.next() else { return Ok(None) };
+        let mut spec = Spec::new(text);
+        for w in it {
+            if let Some(m) = Match::parse(w) {
+                spec.mode = Some(m);
+            } else {
+                spec.priority = w.parse().map_err(|_| format!("无法识别 '{}'", w))?;
+            }
+        }
+        Ok(Some(spec))
+    }
+}
+
+impl From<Target> for Spec {
+    fn from(t: Target) -> Spec {
+        Spec { text: t.raw, mode: Some(t.mode), priority: t.priority }
+    }
+}
+
+/// 两端目标中前缀与后缀的分隔符（输入也接受 `…`）
+pub const ELLIPSIS: &str = "...";
+/// 两端目标中的占位符，其后的字符落在固定偏移处
+pub const ANY: char = '?';
+
+/// 搜索目标: `raw` 为用户输入（已规范化）
+#[derive(Clone, Debug)]
+pub struct Target {
+    pub raw: String,
+    pub mode: Match,
+    /// 同一地址命中多个目标时优先报告数值大的
+    pub priority: u32,
+}
+
+impl Target {
+    /// 按当前设置校验并构造目标，字符无效时返回 None
+    pub fn new(s: &str, settings: &Settings) -> Option<Target> {
+        Target::from_spec(&Spec::new(s), settings)
+    }
+
+    pub fn from_spec(spec: &Spec, settings: &Settings) -> Option<Target> {
+        Target::parse(spec, settings).ok()
+    }
+
+    /// 同 [`Target::from_spec`]，失败时给出原因
+    pub fn parse(spec: &Spec, settings: &Settings) -> Result<Target, Error> {
+        let text = spec.text.trim();
+        let mut mode = spec.mode.unwrap_or(settings.match_mode);
+        // 字面目标中写了 `...` 即为两端目标
+        let text = &text.replace('…', ELLIPSIS);
+        if mode != Match::Pattern && text.contains(ELLIPSIS) { mode = Match::Both; }
+        let raw = if mode == Match::Both {
+            let (head, tail) = text.split_once(ELLIPSIS).unwrap_or((text, ""));
+            let side = |s: &str| -> Option<String> {
+                let fixed: String = s.chars().filter(|&c| c != ANY).collect();
+                let v = validate(&fixed, settings.addr_type, settings.ignore_case)?;
+                // 校验后的字符按原位置放回占位符之间
+                let mut it = v.chars();
+                Some(s.chars().map(|c| if c == ANY { ANY } else { it.next().unwrap() }).collect())
+            };
+            match (side(head), side(tail)) {
+                (Some(h), Some(t)) if h.chars().chain(t.chars()).any(|c| c != ANY) => format!("{}{}{}", h, ELLIPSIS, t),
+                (Some(_), Some(_)) => return Err(Error::EmptyTarget(text.to_string())),
+                _ => return Err(Error::InvalidTarget(text.to_string())),
+            }
+        } else if mode == Match::Pattern {
+            // Bech32 地址只有小写，与字面目标一样接受大写输入
+            let p = if is_bech32(settings.addr_type) { text.to_lowercase() } else { text.to_string() };
+            Pattern::compile(&p, settings).map_err(|e| Error::InvalidPattern(text.to_string(), e))?;
+            p
+        } else {
+            validate(text, settings.addr_type, settings.ignore_case).filter(|v| !v.is_empty())
+                .ok_or_else(|| Error::InvalidTarget(text.to_string()))?
+        };
+        let t = Target { raw, mode, priority: spec.priority };
+        // Base58 前缀对应的数值区间可能为空，如 P2SH 的 `3z`
+        if let Some(l) = t.lead() {
+            if prefix_exact(l, settings) == Some(0.0) {
+                return Err(Error::Impossible(t.full(settings), prefix_why(l, settings)));
+            }
+        }
+        Ok(t)
+    }
+
+    /// 地址开头须出现的固定字符: 前缀目标本身，或两端目标开头到第一个占位符之前的部分
+    fn lead(&self) -> Option<&str> {
+        match self.mode {
+            Match::Prefix => Some(&self.raw),
+            Match::Both => self.ends().map(|(h, _)| lead(h)).filter(|l| !l.is_empty()),
+            _ => None,
+        }
+    }
+
+    /// 期望尝试次数: 字面目标为字符集大小的长度次方（忽略大小写时按写法数折算），模式按地址模型估算，
+    /// 两端目标为各固定字符概率之积。Base58 开头的固定字符按其数值区间精确计算
+    pub fn expected(&self, settings: &Settings) -> u64 {
+        let (a, nocase) = (settings.addr_type, settings.ignore_case);
+        if self.mode == Match::Pattern {
+            return Pattern::compile(&self.raw, settings).map_or(u64::MAX, |p| p.expected());
+        }
+        let fixed: String = self.raw.chars().filter(|&c| c != ANY && c != '.').collect();
+        let Some(lead) = self.lead() else { return exp(&fixed, a, nocase) };
+        match prefix_exact(lead, settings) {
+            // 其余固定字符仍按均匀分布
+            Some(p) => {
+                let rest = exp(&fixed[lead.len()..], a, nocase) as f64;
+                (rest / p).round().min(u64::MAX as f64) as u64
+            }
+            None => exp(&fixed, a, nocase),
+        }
+    }
+
+    /// 完整显示形式: 前缀目标带上地址固定前缀。测试网 Legacy 有 m、n 两种开头，写成 `mabc|nabc`
+    pub fn full(&self, settings: &Settings) -> String {
+        match (self.mode, settings.addr_type, is_main(settings.network)) {
+            (Match::Prefix | Match::Both, Addr::Legacy, false) => format!("m{0}|n{0}", self.raw),
+            (Match::Prefix | Match::Both, a, _) => format!("{}{}", pfx(a, settings.network), self.raw),
+            _ => self.raw.clone(),
+        }
+    }
+
+    /// 目标字符数；两端目标只计固定字符，不含分隔符和占位符
+    pub fn fixed_len(&self) -> usize {
+        match self.ends() {
+            Some((h, t)) => h.chars().chain(t.chars()).filter(|&c| c != ANY).count(),
+            None => self.raw.chars().count(),
+        }
+    }
+
+    /// 两端目标的前缀与后缀部分（可含占位符）；其他目标返回 None
+    pub fn ends(&self) -> Option<(&str, &str)> {
+        if self.mode != Match::Both { return None; }
+        self.raw.split_once(ELLIPSIS).or(Some((&self.raw, "")))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Settings {
+    pub addr_type: Addr,
+    pub network: Network,
+    pub match_mode: Match,
+    pub output: Out,
+    pub rng_mode: RngMode,
+    pub threads: usize,
+    pub batch_size: u64,
+    pub collect: Collect,
+    pub keygen: KeyGen,
+    /// Base58 目标忽略大小写（Bech32 本身只有小写，不受影响）
+    pub ignore_case: bool,
+    /// 后缀 / 包含匹配是否包括末尾的校验和部分（地址开头的 HRP / 版本字符始终不参与）
+    pub checksum: bool,
+    /// 最长运行时间（不含暂停），到时停止
+    pub max_time: Option<Duration>,
+    /// 最多尝试次数
+    pub max_attempts: Option<u64>,
+    /// 命中任一目标的累计概率达到该值 (0-1) 时停止
+    pub max_prob: Option<f64>,
+    /// 美观评分不低于该值的地址也算命中（见 [`crate::score`]），可以不设目标
+    pub min_score: Option<f64>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            addr_type: Addr::Taproot,
+            network: Network::Bitcoin,
+            match_mode: Match::Prefix,
+            output: Out::Mnemonic,
+            rng_mode: RngMode::Secure,
+            threads: num_cpus::get(),
+            batch_size: 512,
+            collect: Collect::Total(1),
+            keygen: KeyGen::Random,
+            ignore_case: false,
+            checksum: true,
+            max_time: None,
+            max_attempts: None,
+            max_prob: None,
+            min_score: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(a: Addr, n: Network) -> Settings {
+        Settings { addr_type: a, network: n, ..Settings::default() }
+    }
+
+    #[test]
+    fn full_shows_real_beginnings() {
+        let t = |raw: &str, m: Match| Target { raw: raw.into(), mode: m, priority: 0 };
+        assert_eq!(t("qqq", Match::Prefix).full(&settings(Addr::SegWit, Network::Testnet)), "tb1qqqq");
+        assert_eq!(t("abc", Match::Prefix).full(&settings(Addr::Legacy, Network::Bitcoin)), "1abc");
+        assert_eq!(t("abc", Match::Prefix).full(&settings(Addr::Legacy, Network::Testnet)), "mabc|nabc");
+        assert_eq!(t("ab...cd", Match::Both).full(&settings(Addr::Legacy, Network::Signet)), "mab...cd|nab...cd");
+        assert_eq!(t("abc", Match::Suffix).full(&settings(Addr::Legacy, Network::Testnet)), "abc");
+        assert_eq!(t("abc", Match::Prefix).full(&settings(Addr::P2SH, Network::Testnet)), "2abc");
+    }
+
+    fn parse(text: &str, a: Addr) -> Result<Target, Error> {
+        Target::parse(&Spec::new(text), &settings(a, Network::Bitcoin))
+    }
+
+    #[test]
+    fn parse_both_ends() {
+        let t = parse("ac...cd", Addr::SegWit).unwrap();
+        assert_eq!((t.mode, t.raw.as_str(), t.ends()), (Match::Both, "ac...cd", Some(("ac", "cd"))));
+        // … 与大写输入规范化
+        assert_eq!(parse("AC…CD", Addr::SegWit).unwrap().raw, "ac...cd");
+        // 只有一端
+        let t = parse("...cd", Addr::SegWit).unwrap();
+        assert_eq!((t.mode, t.ends(), t.lead()), (Match::Both, Some(("", "cd")), None));
+        let t = parse("ac...", Addr::SegWit).unwrap();
+        assert_eq!((t.ends(), t.lead()), (Some(("ac", "")), Some("ac")));
+        // 单独指定 both 时不写 ... 也是只有开头
+        let t = Target::parse(&Spec { text: "acd".into(), mode: Some(Match::Both), priority: 0 }, &Settings::default()).unwrap();
+        assert_eq!(t.ends(), Some(("acd", "")));
+        // Base58 保留大小写
+        assert_eq!(parse("Ab...cD", Addr::Legacy).unwrap().raw, "Ab...cD");
+    }
+
+    #[test]
+    fn parse_placeholders() {
+        let t = parse("a?c...?d", Addr::SegWit).unwrap();
+        assert_eq!((t.raw.as_str(), t.fixed_len(), t.lead()), ("a?c...?d", 3, Some("a")));
+        assert_eq!(t.expected(&settings(Addr::SegWit, Network::Bitcoin)), 32u64.pow(3));
+        // 占位符之间的字符照常校验和规范化
+        assert_eq!(parse("?A?...Q", Addr::SegWit).unwrap().raw, "?a?...q");
+        assert!(parse("?...a", Addr::SegWit).unwrap().lead().is_none());
+        // 没有 ... 的普通目标不接受占位符
+        assert!(matches!(parse("a?c", Addr::SegWit), Err(Error::InvalidTarget(_))));
+    }
+
+    #[test]
+    fn parse_rejects() {
+        // 两端都没有固定字符
+        for t in ["...", "?...", "??...?", "…"] {
+            assert!(matches!(parse(t, Addr::SegWit), Err(Error::EmptyTarget(_))), "{}", t);
+        }
+        // 无效字符，或多个分隔符
+        for t in ["ac...cb", "b...", "ac...cd...ef", "a.c...d"] {
+            assert!(matches!(parse(t, Addr::SegWit), Err(Error::InvalidTarget(_))), "{}", t);
+        }
+        for t in ["0a...b", "a...l", "a...b...c"] {
+            assert!(matches!(parse(t, Addr::Legacy), Err(Error::InvalidTarget(_))), "{}", t);
+        }
+        // Base58 开头超出范围
+        assert!(matches!(parse("z...a", Addr::P2SH), Err(Error::Impossible(..))));
+    }
+}
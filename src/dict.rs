@@ -0,0 +1,166 @@
+//! 词典模式
+//!
+//! 不指定具体目标，而是读入一个词表（英文、拼音、品牌名等），地址开头或数据部分出现任一足够长的词即算命中。
+//! 每个词作为包含目标，优先级为词长，同一地址出现多个词时报告最长的。结果按词长与罕见度排序，
+//! 方便从中挑一个好记的地址。
+//!
+//! ```text
+//! # 每行一个词，之后的内容（如词频）忽略
+//! satoshi
+//! moon 12345
+//! ```
+
+use crate::addr::{exp, is_bech32, pfx_len, validate, Addr};
+use crate::config::{Match, Settings, Spec, Target};
+use crate::search::Found;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+/// 默认最短词长
+pub const MIN_LEN: usize = 4;
+
+/// 读取结果: 可用的词（按词长从长到短）与被过滤掉的数量
+#[derive(Clone, Debug, Default)]
+pub struct Words {
+    pub words: Vec<String>,
+    /// 短于最短词长
+    pub short: usize,
+    /// 含当前地址类型不支持的字符
+    pub invalid: usize,
+    /// 规范化后重复
+    pub dups: usize,
+}
+
+impl Words {
+    /// 每个词作为包含目标，优先级为词长
+    pub fn specs(&self) -> Vec<Spec> {
+        self.words.iter().map(|w| Spec { text: w.clone(), mode: Some(Match::Contains), priority: w.chars().count() as u32 }).collect()
+    }
+}
+
+/// 逐行取第一个词，用 `validate()` 按地址类型过滤；`min` 为最短词长
+pub fn read(r: impl BufRead, min: usize, s: &Settings) -> io::Result<Words> {
+    let mut out = Words::default();
+    for line in r.lines() {
+        let line = line?;
+        let Some(w) = line.split('#').next().unwrap_or("").split_whitespace().next() else { continue };
+        if w.chars().count() < min { out.short += 1; continue; }
+        match validate(w, s.addr_type, s.ignore_case) {
+            Some(v) => out.words.push(v),
+            None => out.invalid += 1,
+        }
+    }
+    // 忽略大小写时 `Moon` 与 `moon` 匹配的地址相同
+    let nocase = s.ignore_case && !is_bech32(s.addr_type);
+    let key = |w: &String| if nocase { w.to_lowercase() } else { w.clone() };
+    let n = out.words.len();
+    out.words.sort_unstable_by(|a, b| b.len().cmp(&a.len()).then_with(|| key(a).cmp(&key(b))));
+    out.words.dedup_by(|a, b| key(a) == key(b));
+    out.dups = n - out.words.len();
+    Ok(out)
+}
+
+pub fn load(path: impl AsRef<Path>, min: usize, s: &Settings) -> io::Result<Words> {
+    read(BufReader::new(File::open(path)?), min, s)
+}
+
+/// 命中词的评分
+#[derive(Clone, Debug)]
+pub struct Score {
+    pub word: String,
+    pub len: usize,
+    /// 词在地址中的字符位置
+    pub at: usize,
+    /// 紧跟在固定前缀之后，即作为前缀出现
+    pub lead: bool,
+    /// 罕见度: 该词出现在这种位置的期望尝试次数。作为前缀时按前缀算，否则按数据部分任意位置算
+    pub rarity: f64,
+}
+
+/// 后缀 / 包含匹配区域的字符数，与 [`crate::matcher`] 中预筛用的一致
+fn region_len(s: &Settings) -> usize {
+    let data = match s.addr_type { Addr::Taproot => 52, Addr::SegWit => 32, _ => 27 };
+    if s.checksum { data + 6 } else { data }
+}
+
+/// 给一个结果评分；目标不是直接出现在地址里的字面词（如模式）时返回 None
+pub fn score(f: &Found, s: &Settings) -> Option<Score> {
+    let nocase = s.ignore_case && !is_bech32(s.addr_type);
+    let skip = pfx_len(s.addr_type, s.network);
+    let (addr, word) = if nocase { (f.addr.to_lowercase(), f.target.to_lowercase()) } else { (f.addr.clone(), f.target.clone()) };
+    let at = skip + addr.get(skip..)?.find(&word)?;
+    let len = word.chars().count();
+    let lead = at == skip;
+    let rarity = if lead {
+        // 前缀按 Base58 数值区间精确计算
+        Target { raw: f.target.clone(), mode: Match::Prefix, priority: 0 }.expected(s) as f64
+    } else {
+        let spots = region_len(s).saturating_sub(len) + 1;
+        exp(&f.target, s.addr_type, s.ignore_case) as f64 / spots as f64
+    };
+    Some(Score { word: f.target.clone(), len, at, lead, rarity })
+}
+
+/// 按词长、再按罕见度从高到低排序，返回结果下标及评分
+pub fn rank(found: &[Found], s: &Settings) -> Vec<(usize, Score)> {
+    let mut v: Vec<(usize, Score)> = found.iter().enumerate().filter_map(|(i, f)| Some((i, score(f, s)?))).collect();
+    v.sort_by(|a, b| b.1.len.cmp(&a.1.len).then(b.1.rarity.total_cmp(&a.1.rarity)).then(a.0.cmp(&b.0)));
+    v
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn settings(a: Addr, nocase: bool) -> Settings {
+        Settings { addr_type: a, ignore_case: nocase, ..Settings::default() }
+    }
+
+    const LIST: &str = "\
+# 注释行
+
+satoshi
+moon 12345
+Moon
+  acme   # 行尾注释
+ACME
+ab
+b00k
+lolz
+ace7
+";
+
+    #[test]
+    fn read_filters_by_addr_type() {
+        let w = read(Cursor::new(LIST), MIN_LEN, &settings(Addr::Legacy, false)).unwrap();
+        // 按词长从长到短；Base58 区分大小写，Moon 与 moon 不算重复
+        assert_eq!(w.words, ["satoshi", "ACME", "Moon", "ace7", "acme", "moon"]);
+        assert_eq!((w.short, w.invalid, w.dups), (1, 2, 0));
+
+        // 忽略大小写时 l 可以写成 L，大小写不同的写法算重复
+        let w = read(Cursor::new(LIST), MIN_LEN, &settings(Addr::Legacy, true)).unwrap();
+        assert_eq!(w.words.len(), 5);
+        assert!(w.words.contains(&"lolz".to_string()));
+        assert_eq!((w.short, w.invalid, w.dups), (1, 1, 2));
+
+        // Bech32 没有 b / i / o，统一成小写
+        let w = read(Cursor::new(LIST), MIN_LEN, &settings(Addr::SegWit, false)).unwrap();
+        assert_eq!(w.words, ["ace7", "acme"]);
+        assert_eq!((w.short, w.invalid, w.dups), (1, 5, 1));
+
+        let w = read(Cursor::new(LIST), 6, &settings(Addr::SegWit, false)).unwrap();
+        assert!(w.words.is_empty());
+        assert_eq!((w.short, w.invalid), (8, 1));
+    }
+
+    #[test]
+    fn specs_prefer_longer_words() {
+        let w = read(Cursor::new(LIST), MIN_LEN, &settings(Addr::Legacy, false)).unwrap();
+        let specs = w.specs();
+        assert!(specs.iter().all(|s| s.mode == Some(Match::Contains)));
+        assert_eq!(specs[0], Spec { text: "satoshi".into(), mode: Some(Match::Contains), priority: 7 });
+        assert_eq!(specs.last().unwrap().priority, 4);
+    }
+}
@@ -0,0 +1,399 @@
+//! secp256k1 域运算与批量点加
+//!
+//! 增量模式下每批只做一次标量乘法得到 P = kG，之后用预计算表 iG 做点加
+//! 得到 k+1, k+2, … 的公钥；所有点加共享一次模逆（Montgomery 批量求逆）。
+
+use bitcoin::secp256k1::{PublicKey, Secp256k1, SecretKey, Signing};
+
+/// 2^256 - p
+const C: u128 = 0x1_0000_03D1;
+const P: [u64; 4] = [0xFFFF_FFFE_FFFF_FC2F, u64::MAX, u64::MAX, u64::MAX];
+/// p - 2，用于费马小定理求逆
+const P_MINUS_2: [u64; 4] = [0xFFFF_FFFE_FFFF_FC2D, u64::MAX, u64::MAX, u64::MAX];
+
+/// β: 模 p 的三次单位根，(x, y) ↦ (βx, y) 等价于私钥乘以 λ
+const BETA: Fe = Fe([0xC139_6C28_7195_01EE, 0x9CF0_4975_12F5_8995, 0x6E64_479E_AC34_34E9, 0x7AE9_6A2B_657C_0710]);
+/// λ: 模 n 的三次单位根（大端）
+pub const LAMBDA: [u8; 32] = [
+    0x53, 0x63, 0xad, 0x4c, 0xc0, 0x5c, 0x30, 0xe0, 0xa5, 0x26, 0x1c, 0x02, 0x88, 0x12, 0x64, 0x5a,
+    0x12, 0x2e, 0x22, 0xea, 0x20, 0x81, 0x66, 0x78, 0xdf, 0x02, 0x96, 0x7c, 0x1b, 0x23, 0xbd, 0x72,
+];
+
+/// 模 p 域元素，小端 64 位分组，始终保持完全约简
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Fe([u64; 4]);
+
+impl Fe {
+    pub const ONE: Fe = Fe([1, 0, 0, 0]);
+
+    pub fn from_be_bytes(b: &[u8]) -> Fe {
+        let mut r = [0u64; 4];
+        for (i, limb) in r.iter_mut().enumerate() {
+            let off = 24 - i * 8;
+            *limb = u64::from_be_bytes(b[off..off + 8].try_into().unwrap());
+        }
+        Fe(r).normalize()
+    }
+
+    pub fn to_be_bytes(self) -> [u8; 32] {
+        let mut b = [0u8; 32];
+        for i in 0..4 {
+            b[24 - i * 8..32 - i * 8].copy_from_slice(&self.0[i].to_be_bytes());
+        }
+        b
+    }
+
+    pub fn is_zero(&self) -> bool { self.0 == [0; 4] }
+    pub fn is_odd(&self) -> bool { self.0[0] & 1 == 1 }
+
+    fn ge_p(&self) -> bool {
+        for i in (0..4).rev() {
+            if self.0[i] != P[i] { return self.0[i] > P[i]; }
+        }
+        true
+    }
+
+    fn sub_p(self) -> Fe {
+        let mut r = [0u64; 4];
+        let mut borrow = false;
+        for (i, limb) in r.iter_mut().enumerate() {
+            let (v, b1) = self.0[i].overflowing_sub(P[i]);
+            let (v, b2) = v.overflowing_sub(borrow as u64);
+            *limb = v;
+            borrow = b1 || b2;
+        }
+        Fe(r)
+    }
+
+    fn normalize(self) -> Fe { if self.ge_p() { self.sub_p() } else { self } }
+
+    /// 加上 2^256 - p（调用方保证不会溢出）
+    fn add_c(mut self) -> Fe {
+        let mut carry = C;
+        for limb in self.0.iter_mut() {
+            let v = *limb as u128 + carry;
+            *limb = v as u64;
+            carry = v >> 64;
+        }
+        self
+    }
+
+    pub fn sub(&self, o: &Fe) -> Fe {
+        let mut r = [0u64; 4];
+        let mut borrow = false;
+        for (i, limb) in r.iter_mut().enumerate() {
+            let (v, b1) = self.0[i].overflowing_sub(o.0[i]);
+            let (v, b2) = v.overflowing_sub(borrow as u64);
+            *limb = v;
+            borrow = b1 || b2;
+        }
+        if !borrow { return Fe(r); }
+        // 借位时结果多了 2^256，减去 C 即等于加上 p
+        let mut borrow = C;
+        for limb in r.iter_mut() {
+            let (v, b) = limb.overflowing_sub(borrow as u64);
+            *limb = v;
+            borrow = (borrow >> 64) + b as u128;
+        }
+        Fe(r)
+    }
+
+    pub fn mul(&self, o: &Fe) -> Fe {
+        let mut t = [0u64; 8];
+        for i in 0..4 {
+            let mut carry = 0u128;
+            for j in 0..4 {
+                let v = t[i + j] as u128 + self.0[i] as u128 * o.0[j] as u128 + carry;
+                t[i + j] = v as u64;
+                carry = v >> 64;
+            }
+            t[i + 4] = carry as u64;
+        }
+        reduce(t)
+    }
+
+    pub fn sqr(&self) -> Fe { self.mul(self) }
+
+    /// 模逆（费马小定理），零元返回零
+    pub fn inv(&self) -> Fe {
+        let mut r = Fe::ONE;
+        for i in (0..4).rev() {
+            for bit in (0..64).rev() {
+                r = r.sqr();
+                if (P_MINUS_2[i] >> bit) & 1 == 1 { r = r.mul(self); }
+            }
+        }
+        r
+    }
+}
+
+/// 512 位乘积约简: 2^256 ≡ C (mod p)
+fn reduce(t: [u64; 8]) -> Fe {
+    let mut r = [0u64; 4];
+    let mut carry = 0u128;
+    for i in 0..4 {
+        let v = t[i] as u128 + t[i + 4] as u128 * C + carry;
+        r[i] = v as u64;
+        carry = v >> 64;
+    }
+    // 第二轮: 高位 (< 2^34) 再乘 C 折回
+    let mut c = carry * C;
+    for limb in r.iter_mut() {
+        let v = *limb as u128 + c;
+        *limb = v as u64;
+        c = v >> 64;
+    }
+    if c != 0 { Fe(r).add_c() } else { Fe(r).normalize() }
+}
+
+/// 批量求逆: 输入中不能有零元
+pub fn batch_inv(xs: &mut [Fe], acc: &mut Vec<Fe>) {
+    acc.clear();
+    let mut run = Fe::ONE;
+    for x in xs.iter() {
+        run = run.mul(x);
+        acc.push(run);
+    }
+    let mut inv = run.inv();
+    for i in (0..xs.len()).rev() {
+        let prev = if i == 0 { Fe::ONE } else { acc[i - 1] };
+        let x = xs[i];
+        xs[i] = inv.mul(&prev);
+        inv = inv.mul(&x);
+    }
+}
+
+/// 仿射坐标点
+#[derive(Clone, Copy, Debug)]
+pub struct Point { pub x: Fe, pub y: Fe }
+
+impl Point {
+    pub fn from_pubkey(pk: &PublicKey) -> Point {
+        let b = pk.serialize_uncompressed();
+        Point { x: Fe::from_be_bytes(&b[1..33]), y: Fe::from_be_bytes(&b[33..65]) }
+    }
+
+    /// P, λP, λ²P 的 x 坐标（三者 y 相同）
+    pub fn endo_x(&self) -> [Fe; 3] {
+        let bx = self.x.mul(&BETA);
+        [self.x, bx, bx.mul(&BETA)]
+    }
+}
+
+/// 由 x 坐标和 y 奇偶性得到 33 字节压缩公钥
+pub fn compress(x: &Fe, odd: bool) -> [u8; 33] {
+    let mut out = [0u8; 33];
+    out[0] = if odd { 0x03 } else { 0x02 };
+    out[1..].copy_from_slice(&x.to_be_bytes());
+    out
+}
+
+/// 增量点加器: 从 P = kG 出发得到 k, k+1, …, k+m-1 的公钥
+pub struct Walker {
+    /// iG, i = 1..m
+    table: Vec<Point>,
+    dx: Vec<Fe>,
+    acc: Vec<Fe>,
+}
+
+impl Walker {
+    pub fn new<C: Signing>(secp: &Secp256k1<C>, m: usize) -> Walker {
+        let table = (1..m.max(1) as u64).map(|i| {
+            let mut b = [0u8; 32];
+            b[24..].copy_from_slice(&i.to_be_bytes());
+            let sk = SecretKey::from_slice(&b).unwrap();
+            Point::from_pubkey(&PublicKey::from_secret_key(secp, &sk))
+        }).collect::<Vec<_>>();
+        let n = table.len();
+        Walker { table, dx: Vec::with_capacity(n), acc: Vec::with_capacity(n) }
+    }
+
+    /// 计算 k+i (i = 0..m) 对应的仿射点写入 `out`；遇到 P = ±iG 时返回 false（概率可忽略）
+    pub fn batch<C: Signing>(&mut self, secp: &Secp256k1<C>, k: &SecretKey, out: &mut Vec<Point>) -> bool {
+        let p = Point::from_pubkey(&PublicKey::from_secret_key(secp, k));
+        out.clear();
+        out.push(p);
+
+        self.dx.clear();
+        self.dx.extend(self.table.iter().map(|t| t.x.sub(&p.x)));
+        if self.dx.iter().any(Fe::is_zero) { return false; }
+        batch_inv(&mut self.dx, &mut self.acc);
+
+        for (t, inv) in self.table.iter().zip(&self.dx) {
+            let lam = t.y.sub(&p.y).mul(inv);
+            let x = lam.sqr().sub(&p.x).sub(&t.x);
+            let y = lam.mul(&p.x.sub(&x)).sub(&p.y);
+            out.push(Point { x, y });
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::RngCore;
+
+    /// n - 1 (大端)
+    const N_MINUS_1: [u8; 32] = [
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe,
+        0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36, 0x41, 0x40,
+    ];
+
+    /// 参照实现: a + b (mod p)，不经过 `reduce`
+    fn add(a: &Fe, b: &Fe) -> Fe {
+        let mut out = [0u64; 4];
+        let mut carry = 0u128;
+        for (i, limb) in out.iter_mut().enumerate() {
+            let v = a.0[i] as u128 + b.0[i] as u128 + carry;
+            *limb = v as u64;
+            carry = v >> 64;
+        }
+        // 两个小于 p 的数相加最多溢出一次，溢出的 2^256 ≡ C
+        if carry != 0 { Fe(out).add_c() } else { Fe(out).normalize() }
+    }
+
+    /// 参照实现: 逐位加倍相加
+    fn slow_mul(a: &Fe, b: &Fe) -> Fe {
+        let mut r = Fe([0; 4]);
+        for i in (0..4).rev() {
+            for bit in (0..64).rev() {
+                r = add(&r, &r);
+                if (b.0[i] >> bit) & 1 == 1 { r = add(&r, a); }
+            }
+        }
+        r
+    }
+
+    /// 参照实现: 512 位数逐位折回
+    fn slow_reduce(t: [u64; 8]) -> Fe {
+        let mut r = Fe([0; 4]);
+        for i in (0..8).rev() {
+            for bit in (0..64).rev() {
+                r = add(&r, &r);
+                if (t[i] >> bit) & 1 == 1 { r = add(&r, &Fe::ONE); }
+            }
+        }
+        r
+    }
+
+    fn fe(rng: &mut impl RngCore) -> Fe {
+        let mut b = [0u8; 32];
+        rng.fill_bytes(&mut b);
+        Fe::from_be_bytes(&b)
+    }
+
+    /// 靠近 p 与 0 的边界值
+    fn edges() -> Vec<Fe> {
+        let p = |d: u64| Fe([P[0] - d, P[1], P[2], P[3]]);
+        vec![Fe([0; 4]), Fe::ONE, Fe([2, 0, 0, 0]), p(1), p(2), p(0x3D1), Fe([0, 0, 0, 1 << 63]), Fe([u64::MAX, u64::MAX, u64::MAX, 0]), BETA]
+    }
+
+    #[test]
+    fn from_bytes_reduces() {
+        assert_eq!(Fe::from_be_bytes(&Fe(P).to_be_bytes()), Fe([0; 4]));
+        // 2^256 - 1 = p + C - 1
+        assert_eq!(Fe::from_be_bytes(&[0xff; 32]), Fe([0x1_0000_03D0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn sub_matches_reference() {
+        let mut rng = rand::thread_rng();
+        let mut xs = edges();
+        xs.extend((0..20).map(|_| fe(&mut rng)));
+        for a in &xs {
+            for b in &xs {
+                let d = a.sub(b);
+                assert!(!d.ge_p());
+                // (a - b) + b == a
+                assert_eq!(add(&d, b), *a, "{:?} - {:?}", a, b);
+            }
+        }
+        assert_eq!(Fe([0; 4]).sub(&Fe::ONE), Fe([P[0] - 1, P[1], P[2], P[3]]));
+    }
+
+    #[test]
+    fn mul_matches_reference() {
+        let mut rng = rand::thread_rng();
+        let mut xs = edges();
+        xs.extend((0..10).map(|_| fe(&mut rng)));
+        for a in &xs {
+            for b in &xs {
+                let m = a.mul(b);
+                assert!(!m.ge_p());
+                assert_eq!(m, slow_mul(a, b), "{:?} * {:?}", a, b);
+            }
+        }
+        // (p - 1)^2 = 1
+        let m1 = Fe([P[0] - 1, P[1], P[2], P[3]]);
+        assert_eq!(m1.sqr(), Fe::ONE);
+    }
+
+    #[test]
+    fn reduce_matches_reference() {
+        let mut rng = rand::thread_rng();
+        // 全 1 会走第二轮之后仍溢出的分支
+        let mut ts = vec![[u64::MAX; 8], [0; 8], [u64::MAX, u64::MAX, u64::MAX, u64::MAX, 0, 0, 0, 0], [0, 0, 0, 0, 0, 0, 0, u64::MAX]];
+        ts.push([P[0], P[1], P[2], P[3], 0, 0, 0, 0]);
+        ts.push([u64::MAX, u64::MAX, u64::MAX, u64::MAX - 1, 0, 0, 0, u64::MAX]);
+        ts.extend((0..50).map(|_| { let mut t = [0u64; 8]; t.iter_mut().for_each(|l| *l = rng.next_u64()); t }));
+        for t in ts { assert_eq!(reduce(t), slow_reduce(t), "{:x?}", t); }
+    }
+
+    #[test]
+    fn inv_and_batch_inv() {
+        let mut rng = rand::thread_rng();
+        let mut xs: Vec<Fe> = edges().into_iter().filter(|x| !x.is_zero()).collect();
+        xs.extend((0..20).map(|_| fe(&mut rng)));
+        for x in &xs { assert_eq!(x.mul(&x.inv()), Fe::ONE, "{:?}", x); }
+        assert!(Fe([0; 4]).inv().is_zero());
+
+        let mut ys = xs.clone();
+        batch_inv(&mut ys, &mut vec![]);
+        for (x, y) in xs.iter().zip(&ys) { assert_eq!(*y, x.inv()); }
+    }
+
+    #[test]
+    fn beta_is_cube_root() {
+        assert_ne!(BETA, Fe::ONE);
+        assert_eq!(BETA.sqr().mul(&BETA), Fe::ONE);
+    }
+
+    /// `Walker` 给出的第 i 个点应与 (k+i)G 一致
+    fn check_walk(secp: &Secp256k1<bitcoin::secp256k1::All>, w: &mut Walker, k: &SecretKey) {
+        let mut out = vec![];
+        assert!(w.batch(secp, k, &mut out));
+        for (i, p) in out.iter().enumerate() {
+            let mut b = [0u8; 32];
+            b[24..].copy_from_slice(&(i as u64).to_be_bytes());
+            let ki = k.add_tweak(&bitcoin::secp256k1::Scalar::from_be_bytes(b).unwrap()).unwrap();
+            let want = PublicKey::from_secret_key(secp, &ki).serialize_uncompressed();
+            assert_eq!(p.x.to_be_bytes(), want[1..33], "k={} i={}", k.display_secret(), i);
+            assert_eq!(p.y.to_be_bytes(), want[33..65], "k={} i={}", k.display_secret(), i);
+        }
+    }
+
+    #[test]
+    fn walker_matches_libsecp() {
+        let secp = Secp256k1::new();
+        let mut rng = rand::thread_rng();
+        let mut w = Walker::new(&secp, 64);
+        let mut ks: Vec<SecretKey> = (0..20).map(|_| SecretKey::new(&mut rng)).collect();
+        let small = |v: u64| { let mut b = [0u8; 32]; b[24..].copy_from_slice(&v.to_be_bytes()); SecretKey::from_slice(&b).unwrap() };
+        ks.push(small(64));
+        ks.push(small(65));
+        // k + i 一直到 n - 1
+        let top = SecretKey::from_slice(&N_MINUS_1).unwrap();
+        ks.push(top.add_tweak(&bitcoin::secp256k1::Scalar::from_be_bytes(small(64).negate().secret_bytes()).unwrap()).unwrap());
+        for k in &ks { check_walk(&secp, &mut w, k); }
+        // k = 1 时 P = G 就在表里，只能用不带表的点加器
+        check_walk(&secp, &mut Walker::new(&secp, 1), &small(1));
+
+        // k = n - 1: k + 1 是无穷远点，x 坐标相同，放弃这一批
+        let mut out = vec![];
+        assert!(!w.batch(&secp, &top, &mut out));
+        // P = iG 时同样放弃
+        assert!(!w.batch(&secp, &small(1), &mut out));
+        assert!(!w.batch(&secp, &small(3), &mut out));
+    }
+}
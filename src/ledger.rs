@@ -0,0 +1,300 @@
+//! 累计工作量账本
+//!
+//! 每次搜索都从 0 开始计数，但尝试之间相互独立，中断前做的功同样有效。账本按
+//! (地址类型, 匹配方式, 规范化后的目标集合) 累计尝试次数与耗时，下次搜索同一组目标时接着算。
+//! 文件里只有地址类型、目标和计数等公开信息，不含任何密钥。
+//!
+//! 多个进程可以同时搜索并写同一个账本: 读改写期间持有旁边 `.lock` 文件的独占锁，
+//! 各自只加上自己的增量。
+
+use crate::addr::is_bech32;
+use crate::config::{Match, Settings, Target};
+use serde_json::{json, Map, Value};
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{env, fs, io};
+
+/// 一组目标的累计工作量
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Work {
+    /// 总尝试次数
+    pub attempts: u64,
+    /// 总耗时（秒）
+    pub secs: f64,
+    /// 搜索次数
+    pub runs: u64,
+    /// 总命中数
+    pub found: u64,
+    /// 最近一次命中之后的尝试次数，下一个结果的概率从这里接着算
+    pub since: u64,
+}
+
+/// 当前目标集合在账本中的条目
+#[derive(Clone, Debug)]
+pub struct Ledger {
+    path: PathBuf,
+    key: String,
+    label: String,
+    /// 本次搜索开始前的累计值
+    pub prior: Work,
+    /// 本次搜索已计入账本的部分
+    saved: Work,
+    /// 搜索中途换了目标时，换之前的计数不属于这组目标
+    start: Work,
+}
+
+/// 账本文件: `BTC_VANITY_LEDGER`，否则为用户目录下的 `.btc-vanity/ledger.json`
+pub fn path() -> PathBuf {
+    if let Some(p) = env::var_os("BTC_VANITY_LEDGER") { return p.into(); }
+    match env::var_os("HOME").or_else(|| env::var_os("USERPROFILE")) {
+        Some(h) => PathBuf::from(h).join(".btc-vanity").join("ledger.json"),
+        None => PathBuf::from("btc-vanity-ledger.json"),
+    }
+}
+
+/// 目标集合的标识: 顺序、重复和优先级不影响结果；忽略大小写时字面目标统一成小写。
+/// 校验和设置只在有后缀 / 包含 / 两端目标时计入
+pub fn key(targets: &[Target], s: &Settings) -> String {
+    let nocase = s.ignore_case && !is_bech32(s.addr_type);
+    let mut lines: Vec<String> = targets.iter().map(|t| {
+        let raw = if nocase && t.mode != Match::Pattern { t.raw.to_lowercase() } else { t.raw.clone() };
+        format!("{:?} {}", t.mode, raw)
+    }).collect();
+    // 评分门槛相当于一个额外的目标
+    lines.extend(s.min_score.map(|m| format!("Score {}", m)));
+    lines.sort_unstable();
+    lines.dedup();
+    let region = targets.iter().any(|t| matches!(t.mode, Match::Suffix | Match::Contains | Match::Both));
+    let head = format!("{:?} {} {} {}", s.addr_type, s.network, nocase, region && s.checksum);
+    // FNV-1a，跨版本稳定
+    let mut h = 0xcbf29ce484222325u64;
+    for b in head.bytes().chain(lines.iter().flat_map(|l| b"\n".iter().copied().chain(l.bytes()))) {
+        h = (h ^ b as u64).wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", h)
+}
+
+impl Ledger {
+    /// 读取该组目标之前的累计值；文件不存在或损坏时从 0 开始
+    pub fn open(targets: &[Target], s: &Settings) -> Ledger {
+        Ledger::open_at(path(), targets, s)
+    }
+
+    fn open_at(path: PathBuf, targets: &[Target], s: &Settings) -> Ledger {
+        let key = key(targets, s);
+        let prior = read(&path).ok().and_then(|m| m.get(&key).map(work)).unwrap_or_default();
+        let mut label: Vec<String> = targets.iter().take(3).map(|t| t.full(s)).collect();
+        if targets.len() > 3 { label.push(format!("等 {} 个", targets.len())); }
+        if let Some(m) = s.min_score { label.push(format!("评分 ≥ {}", m)); }
+        Ledger { path, key, label: label.join(", "), prior, saved: Work::default(), start: Work::default() }
+    }
+
+    /// 搜索中途换成这组目标: 之后传入的计数都是从搜索开始算的，先减去换目标时的值
+    pub fn starting_at(self, attempts: u64, secs: f64, found: u64) -> Ledger {
+        let start = Work { attempts, secs, runs: 0, found, since: 0 };
+        Ledger { saved: start, start, ..self }
+    }
+
+    pub fn path(&self) -> &Path { &self.path }
+
+    /// 之前是否搜索过同一组目标
+    pub fn resumed(&self) -> bool { self.prior.runs > 0 }
+
+    /// 加上本次搜索后的累计值。`hit` 为本次最后一次命中时的尝试次数，没有命中为 None
+    pub fn lifetime(&self, attempts: u64, secs: f64, found: u64, hit: Option<u64>) -> Work {
+        let (p, s) = (self.prior, self.start);
+        Work {
+            attempts: p.attempts + attempts - s.attempts,
+            secs: p.secs + secs - s.secs,
+            runs: p.runs + 1,
+            found: p.found + found - s.found,
+            since: hit.filter(|&h| h >= s.attempts).map_or(p.since + attempts - s.attempts, |h| attempts - h),
+        }
+    }
+
+    /// 把本次搜索到目前为止的计数写入账本，可反复调用，只累加上次写入之后的增量
+    pub fn record(&mut self, attempts: u64, secs: f64, found: u64, hit: Option<u64>) -> io::Result<()> {
+        // 其他进程可能同时在搜索同一组目标: 锁住整个读改写，只加本次的增量而不是覆盖
+        let _lock = lock(&self.path)?;
+        let mut all = read(&self.path)?;
+        let mut w = all.get(&self.key).map(work).unwrap_or_default();
+        let d = attempts - self.saved.attempts;
+        w.attempts += d;
+        w.secs += secs - self.saved.secs;
+        w.found += found - self.saved.found;
+        w.since = hit.filter(|&h| h >= self.start.attempts).map_or(w.since + d, |h| attempts - h);
+        if self.saved.runs == 0 { w.runs += 1; }
+        self.saved = Work { attempts, secs, runs: 1, found, since: 0 };
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        all.insert(self.key.clone(), json!({
+            "label": self.label,
+            "attempts": w.attempts,
+            "secs": w.secs,
+            "runs": w.runs,
+            "found": w.found,
+            "since": w.since,
+            "updated": now,
+        }));
+        write(&self.path, &Value::Object(all))
+    }
+}
+
+fn work(v: &Value) -> Work {
+    let n = |k: &str| v[k].as_u64().unwrap_or(0);
+    Work { attempts: n("attempts"), secs: v["secs"].as_f64().unwrap_or(0.0), runs: n("runs"), found: n("found"), since: n("since") }
+}
+
+/// 文件不存在视为空账本；内容无法解析时报错，避免覆盖掉
+fn read(path: &Path) -> io::Result<Map<String, Value>> {
+    let s = match fs::read_to_string(path) {
+        Ok(s) => s,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Map::new()),
+        Err(e) => return Err(e),
+    };
+    match serde_json::from_str(&s) {
+        Ok(Value::Object(m)) => Ok(m),
+        _ => Err(io::Error::new(io::ErrorKind::InvalidData, "账本格式无效")),
+    }
+}
+
+/// 账本旁边 `.lock` 文件上的独占锁，返回的文件关闭时释放
+fn lock(path: &Path) -> io::Result<File> {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let f = OpenOptions::new().create(true).truncate(false).write(true).open(path.with_extension("lock"))?;
+    f.lock()?;
+    Ok(f)
+}
+
+/// 先写临时文件再改名，中途被打断也不会留下半个文件。临时文件名带进程号，
+/// 不会改走别的进程写到一半的文件
+fn write(path: &Path, v: &Value) -> io::Result<()> {
+    let tmp = path.with_extension(format!("{}.tmp", std::process::id()));
+    fs::write(&tmp, serde_json::to_string_pretty(v)?)?;
+    fs::rename(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::addr::Addr;
+    use std::thread;
+
+    fn target(raw: &str, mode: Match, priority: u32) -> Target { Target { raw: raw.into(), mode, priority } }
+
+    /// 每个测试单独的账本文件，结束时删除
+    struct Temp(PathBuf);
+
+    impl Temp {
+        fn new(name: &str) -> Temp {
+            let p = env::temp_dir().join(format!("btc-vanity-test-{}-{}.json", std::process::id(), name));
+            fs::remove_file(&p).ok();
+            Temp(p)
+        }
+        fn open(&self, ts: &[Target]) -> Ledger { Ledger::open_at(self.0.clone(), ts, &Settings::default()) }
+        fn work(&self, ts: &[Target]) -> Work { self.open(ts).prior }
+    }
+
+    impl Drop for Temp {
+        fn drop(&mut self) {
+            fs::remove_file(&self.0).ok();
+            fs::remove_file(self.0.with_extension("lock")).ok();
+        }
+    }
+
+    #[test]
+    fn key_ignores_order_and_duplicates() {
+        let s = Settings::default();
+        let (a, b) = (target("acd", Match::Prefix, 0), target("xyz", Match::Suffix, 0));
+        let k = key(&[a.clone(), b.clone()], &s);
+        assert_eq!(k, key(&[b.clone(), a.clone()], &s));
+        assert_eq!(k, key(&[b.clone(), a.clone(), b.clone(), a.clone()], &s));
+        // 优先级不影响，匹配方式、地址类型和目标本身影响
+        assert_eq!(k, key(&[target("acd", Match::Prefix, 9), b.clone()], &s));
+        assert_ne!(k, key(&[target("acd", Match::Contains, 0), b.clone()], &s));
+        assert_ne!(k, key(std::slice::from_ref(&a), &s));
+        assert_ne!(k, key(&[a.clone(), b.clone()], &Settings { addr_type: Addr::SegWit, ..s.clone() }));
+        assert_ne!(k, key(&[a.clone(), b.clone()], &Settings { min_score: Some(5.0), ..s.clone() }));
+        // 忽略大小写时字面目标按小写比较
+        let nc = Settings { addr_type: Addr::Legacy, ignore_case: true, ..s.clone() };
+        assert_eq!(key(&[target("Moon", Match::Prefix, 0)], &nc), key(&[target("mOOn", Match::Prefix, 0)], &nc));
+        let cs = Settings { addr_type: Addr::Legacy, ..s };
+        assert_ne!(key(&[target("Moon", Match::Prefix, 0)], &cs), key(&[target("mOOn", Match::Prefix, 0)], &cs));
+        // 固定实现，跨版本不变
+        assert_eq!(k.len(), 16);
+    }
+
+    #[test]
+    fn record_adds_only_deltas() {
+        let t = Temp::new("deltas");
+        let ts = [target("acd", Match::Prefix, 0)];
+        let mut l = t.open(&ts);
+        assert!(!l.resumed());
+        l.record(100, 1.0, 0, None).unwrap();
+        l.record(250, 2.5, 0, None).unwrap();
+        l.record(250, 2.5, 0, None).unwrap();
+        assert_eq!(t.work(&ts), Work { attempts: 250, secs: 2.5, runs: 1, found: 0, since: 250 });
+
+        // 第二次搜索接着累计
+        let mut l = t.open(&ts);
+        assert!(l.resumed());
+        l.record(50, 0.5, 0, None).unwrap();
+        assert_eq!(t.work(&ts), Work { attempts: 300, secs: 3.0, runs: 2, found: 0, since: 300 });
+
+        // 两个进程从同一份读数开始，各加各的
+        let (mut x, mut y) = (t.open(&ts), t.open(&ts));
+        x.record(100, 1.0, 0, None).unwrap();
+        y.record(40, 1.0, 0, None).unwrap();
+        x.record(150, 1.0, 0, None).unwrap();
+        assert_eq!(t.work(&ts).attempts, 490);
+        assert_eq!(t.work(&ts).runs, 4);
+
+        // 中途换成这组目标，之前的计数不算
+        let mut l = t.open(&ts).starting_at(1000, 10.0, 3);
+        l.record(1200, 12.0, 3, None).unwrap();
+        let w = t.work(&ts);
+        assert_eq!((w.attempts, w.found, w.runs), (690, 0, 5));
+        assert!((w.secs - 7.0).abs() < 1e-9);
+        // 其他目标集合不受影响
+        assert_eq!(t.work(&[target("xyz", Match::Prefix, 0)]), Work::default());
+    }
+
+    #[test]
+    fn hit_resets_since() {
+        let t = Temp::new("since");
+        let ts = [target("acd", Match::Prefix, 0)];
+        let mut l = t.open(&ts);
+        l.record(400, 1.0, 0, None).unwrap();
+        assert_eq!(t.work(&ts).since, 400);
+        // 第 900 次命中，之后又做了 100 次
+        l.record(1000, 2.0, 1, Some(900)).unwrap();
+        assert_eq!(t.work(&ts).since, 100);
+        l.record(1500, 3.0, 1, Some(900)).unwrap();
+        let w = t.work(&ts);
+        assert_eq!((w.attempts, w.found, w.since), (1500, 1, 600));
+
+        // 下次搜索从 600 接着算，命中后重新计
+        let l = t.open(&ts);
+        assert_eq!(l.lifetime(200, 1.0, 0, None).since, 800);
+        assert_eq!(l.lifetime(200, 1.0, 1, Some(150)).since, 50);
+        // 换目标之前的命中不算这组目标的
+        let l = t.open(&ts).starting_at(1000, 1.0, 1);
+        assert_eq!(l.lifetime(1300, 2.0, 1, Some(900)).since, 900);
+    }
+
+    #[test]
+    fn concurrent_records_are_not_lost() {
+        let t = Temp::new("lock");
+        let ts = [target("acd", Match::Prefix, 0)];
+        thread::scope(|sc| {
+            for _ in 0..4 {
+                let mut l = t.open(&ts);
+                sc.spawn(move || for i in 1..=50 { l.record(i, 0.0, 0, None).unwrap(); });
+            }
+        });
+        let w = t.work(&ts);
+        assert_eq!((w.attempts, w.runs), (200, 4));
+    }
+}
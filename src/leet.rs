@@ -0,0 +1,124 @@
+//! 形近字替换: 目标里有地址字符集不支持的字符时，换成看起来相近的字符
+//!
+//! Bech32 没有 `b` `i` `o` `1`，Base58 没有 `0` `O` `I` `l`，所以 `bob`、`bitcoin` 这类目标无法直接搜索。
+//! 每个无效字符按下表换成字符集里有的写法，可以只给出建议，也可以展开成全部写法一起搜索。
+
+use crate::addr::{validate, Addr};
+use crate::config::{ANY, ELLIPSIS};
+
+/// 展开的写法数上限
+pub const MAX_VARIANTS: usize = 256;
+
+/// 形近字，按相似程度排列；实际可用的还要按地址类型筛选
+const LOOKALIKE: &[(char, &str)] = &[
+    ('o', "0"),
+    ('O', "0o"),
+    ('0', "oO"),
+    ('i', "l1j"),
+    ('I', "1li"),
+    ('l', "1LI"),
+    ('1', "lI"),
+    ('b', "86"),
+    ('B', "86"),
+];
+
+/// 字符 `c` 本身可用，或是两端目标的分隔符 / 占位符
+fn ok(c: char, a: Addr, nocase: bool) -> bool {
+    c == ANY || ELLIPSIS.contains(c) || c == '…' || validate(&c.to_string(), a, nocase).is_some()
+}
+
+/// 无效字符 `c` 在当前地址类型下可用的替换；`c` 本身可用时返回空
+pub fn subs(c: char, a: Addr, nocase: bool) -> Vec<char> {
+    if ok(c, a, nocase) { return vec![]; }
+    let mut v: Vec<char> = LOOKALIKE.iter().filter(|(k, _)| *k == c).flat_map(|(_, s)| s.chars())
+        .filter(|&x| validate(&x.to_string(), a, nocase).is_some())
+        .collect();
+    // Bech32 不区分大小写，`l` 与 `L` 是同一个字符；Base58 忽略大小写时 `o` 与 `O` 也是
+    let key = |x: &char| validate(&x.to_string(), a, nocase).map(|v| if nocase { v.to_lowercase() } else { v });
+    v.dedup_by(|x, y| key(x) == key(y));
+    v
+}
+
+/// 目标是否有无效字符需要替换
+pub fn needed(s: &str, a: Addr, nocase: bool) -> bool {
+    !s.chars().all(|c| ok(c, a, nocase))
+}
+
+/// 每个无效字符换成最相近的写法，以及换了哪些（如 `b→8`）；没有要换的字符或有字符找不到替换时返回 None
+pub fn suggest(s: &str, a: Addr, nocase: bool) -> Option<(String, Vec<String>)> {
+    let mut out = String::new();
+    let mut swaps = vec![];
+    for c in s.chars() {
+        if ok(c, a, nocase) { out.push(c); continue; }
+        let x = *subs(c, a, nocase).first()?;
+        let swap = format!("{}→{}", c, x);
+        if !swaps.contains(&swap) { swaps.push(swap); }
+        out.push(x);
+    }
+    (!swaps.is_empty()).then_some((out, swaps))
+}
+
+/// 全部可用写法: 每个无效字符依次取各个替换；找不到替换或超过 [`MAX_VARIANTS`] 时返回 None
+pub fn variants(s: &str, a: Addr, nocase: bool) -> Option<Vec<String>> {
+    let mut out = vec![String::new()];
+    for c in s.chars() {
+        let cs = if ok(c, a, nocase) { vec![c] } else { subs(c, a, nocase) };
+        if cs.is_empty() || out.len() * cs.len() > MAX_VARIANTS { return None; }
+        out = out.iter().flat_map(|p| cs.iter().map(move |&x| format!("{}{}", p, x))).collect();
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subs_by_charset() {
+        // Bech32 没有 b / i / o / 1
+        assert_eq!(subs('o', Addr::SegWit, false), ['0']);
+        assert_eq!(subs('O', Addr::SegWit, false), ['0']);
+        assert_eq!(subs('i', Addr::SegWit, false), ['l', 'j']);
+        assert_eq!(subs('I', Addr::SegWit, false), ['l']);
+        assert_eq!(subs('1', Addr::Taproot, false), ['l']);
+        assert_eq!(subs('b', Addr::SegWit, false), ['8', '6']);
+        assert!(subs('l', Addr::SegWit, false).is_empty());
+        // Base58 没有 0 / O / I / l
+        assert_eq!(subs('0', Addr::Legacy, false), ['o']);
+        assert_eq!(subs('O', Addr::Legacy, false), ['o']);
+        assert_eq!(subs('I', Addr::Legacy, false), ['1', 'i']);
+        assert_eq!(subs('l', Addr::Legacy, false), ['1', 'L']);
+        assert!(subs('o', Addr::Legacy, false).is_empty());
+        // 忽略大小写时 l 写成 L 即可，不需要替换
+        assert!(subs('l', Addr::Legacy, true).is_empty());
+        assert_eq!(subs('0', Addr::Legacy, true), ['o']);
+    }
+
+    #[test]
+    fn suggest_picks_closest() {
+        let v = |s: &[&str]| s.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        assert_eq!(suggest("bob", Addr::SegWit, false), Some(("808".into(), v(&["b→8", "o→0"]))));
+        assert_eq!(suggest("m00n", Addr::Legacy, false), Some(("moon".into(), v(&["0→o"]))));
+        assert_eq!(suggest("lIO", Addr::Legacy, false), Some(("11o".into(), v(&["l→1", "I→1", "O→o"]))));
+        // 分隔符与占位符保持原样
+        assert_eq!(suggest("b...?o", Addr::SegWit, false), Some(("8...?0".into(), v(&["b→8", "o→0"]))));
+        // 没有无效字符时不给建议
+        assert_eq!(suggest("acd", Addr::SegWit, false), None);
+        assert_eq!(suggest("ac...?d", Addr::SegWit, false), None);
+        assert_eq!(suggest("Moon", Addr::Legacy, false), None);
+        assert!(!needed("Moon", Addr::Legacy, false));
+        // 有字符找不到替换
+        assert_eq!(suggest("bo!", Addr::SegWit, false), None);
+    }
+
+    #[test]
+    fn variants_expand_all() {
+        assert_eq!(variants("bob", Addr::SegWit, false).unwrap(), ["808", "806", "608", "606"]);
+        assert_eq!(variants("acd", Addr::SegWit, false).unwrap(), ["acd"]);
+        assert_eq!(variants("I0", Addr::Legacy, false).unwrap(), ["1o", "io"]);
+        assert_eq!(variants("b!", Addr::SegWit, false), None);
+        // 2^8 = 256 种还可以，再多就放弃
+        assert_eq!(variants(&"b".repeat(8), Addr::SegWit, false).unwrap().len(), MAX_VARIANTS);
+        assert_eq!(variants(&"b".repeat(9), Addr::SegWit, false), None);
+    }
+}
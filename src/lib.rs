@@ -0,0 +1,62 @@
+//! 比特币靓号地址生成器
+//!
+//! 通过 [`Searcher`] 配置并启动多线程搜索，命中结果经由通道或回调返回。
+
+pub mod addr;
+mod base58;
+pub mod config;
+pub mod dict;
+mod ec;
+pub mod ledger;
+pub mod leet;
+pub mod list;
+mod matcher;
+pub mod odds;
+pub mod pattern;
+pub mod score;
+pub mod search;
+
+pub use addr::{base, charset, checksum_len, deriv, exp, is_bech32, is_main, pfx, pfx_len, validate, Addr, BASE58, BECH32};
+pub use bitcoin::Network;
+pub use config::{Collect, KeyGen, Limit, Match, Out, RngMode, Settings, Spec, Target};
+pub use ledger::{Ledger, Work};
+pub use odds::Odds;
+pub use pattern::Pattern;
+pub use score::Score;
+pub use search::{Found, Handle, Partial, Searcher};
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// 目标包含当前地址类型不支持的字符
+    InvalidTarget(String),
+    /// 除 `...` 和 `?` 占位符外没有固定字符
+    EmptyTarget(String),
+    /// 模式无法编译或不可能匹配（模式, 原因）
+    InvalidPattern(String, String),
+    /// 前缀在该地址类型下永远不会出现（目标, 原因）
+    Impossible(String, String),
+    /// 没有任何目标（也没有开启评分）
+    NoTargets,
+    /// 线程数为 0
+    NoThreads,
+    /// 收集数量为 0
+    NoCount,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidTarget(s) => write!(f, "目标 '{}' 包含无效字符", s),
+            Error::EmptyTarget(s) => write!(f, "目标 '{}' 只有 ... 和 ? 占位符，没有要匹配的字符", s),
+            Error::InvalidPattern(s, why) => write!(f, "模式 '{}' 无效: {}", s, why),
+            Error::Impossible(s, why) => write!(f, "目标 '{}' 不可能出现: {}", s, why),
+            Error::NoTargets => write!(f, "无有效目标"),
+            Error::NoThreads => write!(f, "线程数必须大于 0"),
+            Error::NoCount => write!(f, "收集数量必须大于 0"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,117 @@
+//! 目标列表文件
+//!
+//! 每行一个目标，可跟匹配模式和优先级，`#` 之后为注释:
+//!
+//! ```text
+//! # 公司名
+//! acme
+//! satoshi suffix
+//! moon contains 5
+//! ```
+
+use crate::config::{Match, Settings, Spec, Target};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// 读取结果: 有效目标、出错的行（行号从 1 开始）与重复行数
+#[derive(Clone, Debug, Default)]
+pub struct Loaded {
+    pub specs: Vec<Spec>,
+    pub errors: Vec<(usize, String)>,
+    pub dups: usize,
+}
+
+/// 逐行解析并用 `validate()` 校验，重复的 (目标, 模式) 只保留第一次出现
+pub fn read(r: impl BufRead, s: &Settings) -> io::Result<Loaded> {
+    let mut out = Loaded::default();
+    for (i, line) in r.lines().enumerate() {
+        let spec = match Spec::parse(&line?) {
+            Ok(Some(spec)) => spec,
+            Ok(None) => continue,
+            Err(e) => { out.errors.push((i + 1, e)); continue; }
+        };
+        let t = match Target::parse(&spec, s) {
+            Ok(t) => t,
+            Err(e) => { out.errors.push((i + 1, e.to_string())); continue; }
+        };
+        out.specs.push(Spec::from(t));
+    }
+    dedup(&mut out);
+    Ok(out)
+}
+
+/// 排序下标找出重复项，比哈希集合省内存
+fn dedup(l: &mut Loaded) {
+    fn key(s: &Spec) -> (&str, Option<u8>) { (s.text.as_str(), s.mode.map(|m| m as u8)) }
+    let mut idx: Vec<u32> = (0..l.specs.len() as u32).collect();
+    idx.sort_unstable_by(|&a, &b| key(&l.specs[a as usize]).cmp(&key(&l.specs[b as usize])).then(a.cmp(&b)));
+    let mut keep = vec![true; l.specs.len()];
+    for w in idx.windows(2) {
+        if key(&l.specs[w[0] as usize]) == key(&l.specs[w[1] as usize]) { keep[w[1] as usize] = false; }
+    }
+    let mut it = keep.iter();
+    l.specs.retain(|_| *it.next().unwrap());
+    l.dups = keep.iter().filter(|&&k| !k).count();
+}
+
+pub fn load(path: impl AsRef<Path>, s: &Settings) -> io::Result<Loaded> {
+    read(BufReader::new(File::open(path)?), s)
+}
+
+/// 搜索中监视列表文件，修改时间变了就重新读取
+#[derive(Clone, Debug)]
+pub struct Watch {
+    pub path: PathBuf,
+    mtime: Option<SystemTime>,
+}
+
+impl Watch {
+    pub fn new(path: impl Into<PathBuf>) -> Watch {
+        let path = path.into();
+        let mtime = modified(&path);
+        Watch { path, mtime }
+    }
+
+    /// 自上次调用以来文件是否被修改（删除后重建也算）
+    pub fn changed(&mut self) -> bool {
+        let m = modified(&self.path);
+        if m == self.mtime { return false; }
+        self.mtime = m;
+        // 文件暂时不存在（编辑器先删后写）时等它再出现
+        m.is_some()
+    }
+}
+
+fn modified(p: &Path) -> Option<SystemTime> { fs::metadata(p).and_then(|m| m.modified()).ok() }
+
+/// 目标列表摘要，用于确认界面
+#[derive(Clone, Debug)]
+pub struct Summary {
+    pub total: usize,
+    /// 前缀 / 后缀 / 包含 / 模式 / 两端 各自数量
+    pub by_mode: [usize; 5],
+    pub min_len: usize,
+    pub max_len: usize,
+    pub max_priority: u32,
+    /// 最容易的目标及其期望尝试次数
+    pub easiest: Option<(String, u64)>,
+}
+
+pub fn summarize(targets: &[Target], s: &Settings) -> Summary {
+    let mut by_mode = [0; 5];
+    for t in targets {
+        by_mode[match t.mode { Match::Prefix => 0, Match::Suffix => 1, Match::Contains => 2, Match::Pattern => 3, Match::Both => 4 }] += 1;
+    }
+    let lens = targets.iter().map(|t| t.fixed_len());
+    Summary {
+        total: targets.len(),
+        by_mode,
+        min_len: lens.clone().min().unwrap_or(0),
+        max_len: lens.max().unwrap_or(0),
+        max_priority: targets.iter().map(|t| t.priority).max().unwrap_or(0),
+        easiest: targets.iter().map(|t| (t, t.expected(s))).min_by_key(|&(_, e)| e)
+            .map(|(t, e)| (t.full(s), e)),
+    }
+}
@@ -1,75 +1,19 @@
+mod cli;
+
 use anyhow::Result;
-use bip39::Mnemonic;
-use bitcoin::bip32::{DerivationPath, ExtendedPrivKey};
-use bitcoin::secp256k1::{Secp256k1, SecretKey, XOnlyPublicKey};
-use bitcoin::{Address, Network, PrivateKey, PublicKey};
-use crossterm::event::{self, Event, KeyCode, KeyEvent};
+use btc_vanity::{charset, checksum_len, dict, is_bech32, leet, score, is_main, list, pfx, Addr, Collect, Found, KeyGen, Ledger, Limit, Match, Network, Odds, Out, Partial, RngMode, Searcher, Settings, Spec, Target, Work};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use crossterm::terminal::{self, ClearType};
-use rand::rngs::OsRng;
-use rand::RngCore;
-use rand_xoshiro::rand_core::SeedableRng;
-use rand_xoshiro::Xoshiro256PlusPlus;
 use rustyline::DefaultEditor;
-use std::fmt::Write as FmtWrite;
 use std::io::{self, Write};
-use std::str::FromStr;
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
-use std::sync::{mpsc, Arc};
-use std::thread;
+use std::ops::Range;
+use std::sync::mpsc::RecvTimeoutError;
 use std::time::{Duration, Instant};
 
 const VERSION: &str = "0.2.0";
 const AUTHOR_EMAIL: &str = "mky369258@gmail.com";
 const AUTHOR_GITHUB: &str = "MKY508";
 
-const BECH32: &str = "qpzry9x8gf2tvdw0s3jn54khce6mua7l";
-const BASE58: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
-
-#[derive(Clone, Copy, PartialEq)]
-enum Addr { Taproot, SegWit, Legacy, P2SH }
-
-#[derive(Clone, Copy, PartialEq)]
-enum Match { Prefix, Suffix, Contains }
-
-#[derive(Clone, Copy, PartialEq)]
-enum Out { Mnemonic, Wif, Both }
-
-#[derive(Clone, Copy, PartialEq)]
-enum RngMode { Secure, Fast }
-
-#[derive(Clone)]
-struct Target { raw: String, full: String }
-
-#[derive(Clone)]
-struct Settings {
-    addr_type: Addr,
-    match_mode: Match,
-    output: Out,
-    rng_mode: RngMode,
-    threads: usize,
-    batch_size: u64,
-}
-
-impl Default for Settings {
-    fn default() -> Self {
-        Self {
-            addr_type: Addr::Taproot,
-            match_mode: Match::Prefix,
-            output: Out::Mnemonic,
-            rng_mode: RngMode::Secure,
-            threads: num_cpus::get(),
-            batch_size: 512,
-        }
-    }
-}
-
-struct Found {
-    addr: String,
-    mnemonic: Option<String>,
-    wif: Option<String>,
-    target: String,
-}
-
 fn clear() {
     print!("{}", crossterm::terminal::Clear(ClearType::All));
     print!("{}", crossterm::cursor::MoveTo(0, 0));
@@ -118,49 +62,82 @@ fn pause() {
     input("\n按 Enter 继续...");
 }
 
+/// 在第 `row` 行输出一行并清除行尾（raw 模式下 `println!` 不会回到行首）
+fn put(row: usize, s: &str) {
+    print!("\x1B[{};1H{}\x1B[K", row, s);
+}
+
 fn progress_bar(pct: f64, width: usize) -> String {
     let filled = ((pct / 100.0) * width as f64).min(width as f64) as usize;
     let empty = width.saturating_sub(filled);
     format!("[{}{}]", "=".repeat(filled), " ".repeat(empty))
 }
 
-fn charset(a: Addr) -> &'static str {
-    match a { Addr::Taproot | Addr::SegWit => BECH32, _ => BASE58 }
+fn addr_name(a: Addr, n: Network) -> String {
+    let name = match a { Addr::Taproot => "Taproot", Addr::SegWit => "SegWit", Addr::Legacy => "Legacy", Addr::P2SH => "P2SH" };
+    if is_bech32(a) { format!("{} ({})", name, pfx(a, n)) } else { format!("{} ({}...)", name, pfx(a, n)) }
 }
 
-fn is_bech32(a: Addr) -> bool {
-    matches!(a, Addr::Taproot | Addr::SegWit)
+fn match_name(m: Match) -> &'static str {
+    match m { Match::Prefix => "前缀匹配", Match::Suffix => "后缀匹配", Match::Contains => "包含匹配", Match::Pattern => "模式匹配", Match::Both => "两端匹配" }
 }
 
-fn base(a: Addr) -> u64 {
-    match a { Addr::Taproot | Addr::SegWit => 32, _ => 58 }
+fn out_name(o: Out) -> &'static str {
+    match o { Out::Mnemonic => "助记词", Out::Wif => "私钥 (WIF)", Out::Both => "助记词 + 私钥" }
 }
 
-fn pfx(a: Addr) -> &'static str {
-    match a { Addr::Taproot => "bc1p", Addr::SegWit => "bc1q", Addr::Legacy => "1", Addr::P2SH => "3" }
+fn rng_name(r: RngMode) -> &'static str {
+    match r { RngMode::Secure => "安全 (OsRng)", RngMode::Fast => "快速 (Xoshiro)" }
 }
 
-fn deriv(a: Addr) -> &'static str {
-    match a { Addr::Taproot => "m/86'/0'/0'/0/0", Addr::SegWit => "m/84'/0'/0'/0/0", _ => "m/44'/0'/0'/0/0" }
+fn keygen_name(k: KeyGen) -> &'static str {
+    match k { KeyGen::Incremental => "增量批量 (点加)", KeyGen::Random => "独立随机" }
 }
 
-fn addr_name(a: Addr) -> &'static str {
-    match a { Addr::Taproot => "Taproot (bc1p)", Addr::SegWit => "SegWit (bc1q)", Addr::Legacy => "Legacy (1...)", Addr::P2SH => "P2SH (3...)" }
+fn net_name(n: Network) -> &'static str {
+    match n { Network::Bitcoin => "主网", Network::Testnet => "测试网 (testnet)", Network::Signet => "Signet", _ => "Regtest" }
 }
 
-fn match_name(m: Match) -> &'static str {
-    match m { Match::Prefix => "前缀匹配", Match::Suffix => "后缀匹配", Match::Contains => "包含匹配" }
+fn case_name(s: &Settings) -> &'static str {
+    match (s.ignore_case, is_bech32(s.addr_type)) {
+        (_, true) => "Bech32 只有小写",
+        (true, _) => "忽略 (如 love 也匹配 LoVe)",
+        (false, _) => "区分",
+    }
 }
 
-fn out_name(o: Out) -> &'static str {
-    match o { Out::Mnemonic => "助记词", Out::Wif => "私钥 (WIF)", Out::Both => "助记词 + 私钥" }
+/// 后缀/包含匹配的区域，如 `bc1q [数据 32 位] [校验和 6 位]`
+fn region_name(s: &Settings) -> String {
+    let (p, n) = (pfx(s.addr_type, s.network), checksum_len(s.addr_type));
+    let data = match s.addr_type { Addr::Taproot => "52 位", Addr::SegWit => "32 位", _ => "约 27 位" };
+    if s.checksum {
+        format!("{} 之后的数据 {} + 校验和 {} 位", p, data, n)
+    } else {
+        format!("{} 之后的数据 {}，不含末尾 {} 位校验和", p, data, n)
+    }
 }
 
-fn rng_name(r: RngMode) -> &'static str {
-    match r { RngMode::Secure => "安全 (OsRng)", RngMode::Fast => "快速 (Xoshiro)" }
+fn collect_name(c: Collect) -> String {
+    match c {
+        Collect::Total(1) => "首个命中".into(),
+        Collect::Total(n) => format!("{} 个", n),
+        Collect::PerTarget => "每个目标一个".into(),
+        Collect::Unlimited => "不限 (直到停止)".into(),
+    }
+}
+
+/// 停止条件摘要，如 `2h / 1,000,000 次 / 90%`
+fn limits_name(s: &Settings) -> String {
+    let mut v = vec![];
+    if let Some(d) = s.max_time { v.push(fmt_time(d.as_secs())); }
+    if let Some(n) = s.max_attempts { v.push(format!("{} 次", fmt_num(n))); }
+    if let Some(q) = s.max_prob { v.push(format!("概率 {}%", q * 100.0)); }
+    if v.is_empty() { "无 (直到命中)".into() } else { v.join(" / ") }
 }
 
-fn exp(len: usize, a: Addr) -> u64 { base(a).pow(len as u32) }
+fn limit_name(l: Limit) -> &'static str {
+    match l { Limit::Time => "达到时间上限", Limit::Attempts => "达到尝试次数上限", Limit::Probability => "达到概率上限" }
+}
 
 fn fmt_num(n: u64) -> String {
     let s = n.to_string();
@@ -181,19 +158,17 @@ fn fmt_time(s: u64) -> String {
     }
 }
 
-fn validate(s: &str, a: Addr) -> Option<String> {
-    let cs = charset(a);
-    if is_bech32(a) {
-        // Bech32: 只允许小写
-        let low = s.to_lowercase();
-        if low.chars().all(|c| cs.contains(c)) { Some(low) } else { None }
-    } else {
-        // Base58: 保留大小写
-        if s.chars().all(|c| cs.contains(c)) { Some(s.to_string()) } else { None }
+fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("search") => std::process::exit(cli::search(&args[1..])),
+        Some(other) => {
+            eprintln!("未知命令: {}  (可用: search, 或不带参数进入交互界面)", other);
+            std::process::exit(cli::EXIT_INVALID);
+        }
+        None => {}
     }
-}
 
-fn main() -> Result<()> {
     let mut settings = Settings::default();
 
     loop {
@@ -207,16 +182,20 @@ fn main() -> Result<()> {
         println!("  │      [1] 开始生成                       │");
         println!("  │      [2] 设置                           │");
         println!("  │      [3] 关于                           │");
+        println!("  │      [4] 词典搜索                       │");
+        println!("  │      [5] 美观评分                       │");
         println!("  │      [0] 退出                           │");
         println!("  │                                         │");
         println!("  ╰─────────────────────────────────────────╯");
         println!();
-        println!("  按 1-3 选择  |  0/Esc 退出");
+        println!("  按 1-5 选择  |  0/Esc 退出");
 
         match read_key() {
             Some('1') => generate(&settings),
             Some('2') => settings_menu(&mut settings),
             Some('3') => about(),
+            Some('4') => dictionary(&settings),
+            Some('5') => pretty(&settings),
             Some('0') | Some('q') | Some('\x1b') => { clear(); println!("\n  再见!\n"); break; }
             _ => {}
         }
@@ -232,23 +211,28 @@ fn settings_menu(settings: &mut Settings) {
         println!("  │               设    置                  │");
         println!("  ╰─────────────────────────────────────────╯");
         println!();
-        println!("    [1] 地址类型    {}", addr_name(settings.addr_type));
+        println!("    [1] 地址类型    {}", addr_name(settings.addr_type, settings.network));
         println!("    [2] 匹配模式    {}", match_name(settings.match_mode));
         println!("    [3] 输出格式    {}", out_name(settings.output));
         println!("    [4] 随机源      {}", rng_name(settings.rng_mode));
         println!("    [5] 线程数量    {}", settings.threads);
         println!("    [6] 批处理量    {}", settings.batch_size);
+        println!("    [7] 收集数量    {}", collect_name(settings.collect));
+        println!("    [8] 网络        {}", net_name(settings.network));
+        println!("    [9] 私钥生成    {}", keygen_name(settings.keygen));
+        println!("    [0] 大小写      {}", case_name(settings));
+        println!("    [C] 校验和      {}", if settings.checksum { "后缀/包含匹配包括校验和" } else { "后缀/包含匹配不含校验和" });
+        println!("    [L] 停止条件    {}", limits_name(settings));
         println!();
-        println!("  按 1-6 选择  |  Esc 返回");
+        println!("  按 0-9 / C / L 选择  |  Esc 返回");
 
         match read_key() {
             Some('1') => {
                 clear();
                 println!("\n  选择地址类型:\n");
-                println!("    [1] Taproot (bc1p) - BIP86");
-                println!("    [2] SegWit  (bc1q) - BIP84");
-                println!("    [3] Legacy  (1...) - BIP44");
-                println!("    [4] P2SH    (3...) - BIP44");
+                for (i, (a, bip)) in [(Addr::Taproot, 86), (Addr::SegWit, 84), (Addr::Legacy, 44), (Addr::P2SH, 44)].into_iter().enumerate() {
+                    println!("    [{}] {:<16} - BIP{}", i + 1, addr_name(a, settings.network), bip);
+                }
                 println!();
                 println!("  按 1-4 选择  |  Esc 返回");
                 match read_key() {
@@ -262,15 +246,19 @@ fn settings_menu(settings: &mut Settings) {
             Some('2') => {
                 clear();
                 println!("\n  选择匹配模式:\n");
-                println!("    [1] 前缀匹配 ({}xxx...)", pfx(settings.addr_type));
+                println!("    [1] 前缀匹配 ({}xxx...)", pfx(settings.addr_type, settings.network));
                 println!("    [2] 后缀匹配 (...xxx)");
                 println!("    [3] 包含匹配 (...xxx...)");
+                println!("    [4] 模式匹配 (正则子集, 如 (.)\\1{{5}})");
+                println!("    [5] 两端匹配 ({}xxx...yyy)", pfx(settings.addr_type, settings.network));
                 println!();
-                println!("  按 1-3 选择  |  Esc 返回");
+                println!("  按 1-5 选择  |  Esc 返回");
                 match read_key() {
                     Some('1') => settings.match_mode = Match::Prefix,
                     Some('2') => settings.match_mode = Match::Suffix,
                     Some('3') => settings.match_mode = Match::Contains,
+                    Some('4') => settings.match_mode = Match::Pattern,
+                    Some('5') => settings.match_mode = Match::Both,
                     _ => {}
                 }
             }
@@ -314,7 +302,7 @@ fn settings_menu(settings: &mut Settings) {
                 println!("  输入数字后按 Enter  |  直接按 Esc 返回");
                 let s = input("\n  输入: ");
                 if let Ok(n) = s.parse::<usize>() {
-                    if n >= 1 && n <= max { settings.threads = n; }
+                    if (1..=max).contains(&n) { settings.threads = n; }
                 }
             }
             Some('6') => {
@@ -326,7 +314,83 @@ fn settings_menu(settings: &mut Settings) {
                 println!("  输入数字后按 Enter  |  直接按 Esc 返回");
                 let s = input("\n  输入: ");
                 if let Ok(n) = s.parse::<u64>() {
-                    if n >= 64 && n <= 2048 { settings.batch_size = n; }
+                    if (64..=2048).contains(&n) { settings.batch_size = n; }
+                }
+            }
+            Some('7') => {
+                clear();
+                println!("\n  选择收集数量:\n");
+                println!("    [1] 首个命中即停止");
+                println!("    [2] 指定数量 (去重后共 N 个)");
+                println!("    [3] 每个目标至少一个");
+                println!("    [4] 不限 (搜索中按 Esc/q 停止)");
+                println!();
+                println!("  按 1-4 选择  |  Esc 返回");
+                match read_key() {
+                    Some('1') => settings.collect = Collect::Total(1),
+                    Some('2') => {
+                        let s = input("\n  数量: ");
+                        if let Ok(n) = s.parse::<u64>() {
+                            if n >= 1 { settings.collect = Collect::Total(n); }
+                        }
+                    }
+                    Some('3') => settings.collect = Collect::PerTarget,
+                    Some('4') => settings.collect = Collect::Unlimited,
+                    _ => {}
+                }
+            }
+            Some('8') => {
+                clear();
+                println!("\n  选择网络:\n");
+                println!("    [1] 主网    (bc1 / 1 / 3)");
+                println!("    [2] 测试网  (tb1 / m,n / 2)");
+                println!("    [3] Signet  (tb1 / m,n / 2)");
+                println!("    [4] Regtest (bcrt1 / m,n / 2)");
+                println!();
+                println!("    非主网使用 BIP44 币种 1' 与 tprv/测试网 WIF");
+                println!();
+                println!("  按 1-4 选择  |  Esc 返回");
+                match read_key() {
+                    Some('1') => settings.network = Network::Bitcoin,
+                    Some('2') => settings.network = Network::Testnet,
+                    Some('3') => settings.network = Network::Signet,
+                    Some('4') => settings.network = Network::Regtest,
+                    _ => {}
+                }
+            }
+            Some('9') => {
+                clear();
+                println!("\n  选择私钥生成方式 (仅纯私钥模式):\n");
+                println!("    [1] 增量批量 - 每批一个随机起点 k，依次计算 k+1, k+2…");
+                println!("        点加 + 共享模逆，速度快很多，推荐");
+                println!("    [2] 独立随机 - 每次尝试都重新生成随机私钥");
+                println!();
+                println!("  按 1-2 选择  |  Esc 返回");
+                match read_key() {
+                    Some('1') => settings.keygen = KeyGen::Incremental,
+                    Some('2') => settings.keygen = KeyGen::Random,
+                    _ => {}
+                }
+            }
+            // 只有两种取值，直接切换
+            Some('0') => settings.ignore_case = !settings.ignore_case,
+            Some('c') | Some('C') => settings.checksum = !settings.checksum,
+            Some('l') | Some('L') => {
+                clear();
+                println!("\n  设置停止条件 (达到任一即停止，显示未找到的汇总):\n");
+                println!("    [1] 运行时间    {}", settings.max_time.map_or("不限".into(), |d| fmt_time(d.as_secs())));
+                println!("    [2] 尝试次数    {}", settings.max_attempts.map_or("不限".into(), fmt_num));
+                println!("    [3] 命中概率    {}", settings.max_prob.map_or("不限".into(), |q| format!("{}%", q * 100.0)));
+                println!("    [0] 全部清除");
+                println!();
+                println!("  按 0-3 选择  |  Esc 返回");
+                // 输入留空或无效时取消该项
+                match read_key() {
+                    Some('1') => settings.max_time = cli::parse_time(&input("\n  时长 (如 90、30m、2h、1d): ")),
+                    Some('2') => settings.max_attempts = input("\n  次数: ").replace(',', "").parse().ok().filter(|&n| n >= 1),
+                    Some('3') => settings.max_prob = cli::parse_pct(&input("\n  概率 % (如 90、99.9): ")),
+                    Some('0') => (settings.max_time, settings.max_attempts, settings.max_prob) = (None, None, None),
+                    _ => {}
                 }
             }
             Some('\x1b') => break,
@@ -370,14 +434,29 @@ fn generate(settings: &Settings) {
     println!("  │             输入目标                    │");
     println!("  ╰─────────────────────────────────────────╯");
     println!();
-    println!("    地址前缀: {}", pfx(settings.addr_type));
+    println!("    地址前缀: {}", pfx(settings.addr_type, settings.network));
     println!("    字符集:   {}", charset(settings.addr_type));
     if !is_bech32(settings.addr_type) {
-        println!("    注意:     Base58 区分大小写!");
+        if settings.ignore_case {
+            println!("    大小写:   忽略 (l 没有大写, I/O 没有小写)");
+        } else {
+            println!("    注意:     Base58 区分大小写! (设置中可改为忽略)");
+        }
     }
     println!();
-    println!("    输入目标字符 (多个用逗号分隔)");
-    println!("    例如: test,6666,abc");
+    // 模式里可能有逗号，改用空格分隔
+    let pattern = settings.match_mode == Match::Pattern;
+    if pattern {
+        println!("    输入模式 (多个用空格分隔)，或 @文件路径 从文件读取");
+        println!("    匹配完整地址: . 任意字符  [a-z] 字符类  \\d 数字  (a|b) 分组");
+        println!("    * + ? {{n,m}} 重复  ^ $ 锚定首尾  \\1 引用单字符分组");
+        println!("    例如: ^{}[0-9]{{4}}  (.)\\1{{5}}$", pfx(settings.addr_type, settings.network));
+    } else {
+        println!("    输入目标字符 (多个用逗号分隔)，或 @文件路径 从文件读取");
+        println!("    例如: test,6666,abc  或  @targets.txt");
+        println!("    前缀...后缀 同时匹配两端，? 占位固定位置，如 mky...2024  ab??cd...");
+    }
+    println!("    文件每行: 目标 [prefix|suffix|contains|pattern|both] [优先级]");
     println!();
     println!("  ←→ 移动光标  |  Enter 确认  |  留空按 Enter 返回");
     println!();
@@ -385,49 +464,248 @@ fn generate(settings: &Settings) {
     let raw = input("  目标: ");
     if raw.is_empty() { return; }
 
-    let targets: Vec<Target> = raw.split(',')
-        .filter_map(|s| {
-            let s = s.trim();
-            if let Some(v) = validate(s, settings.addr_type) {
-                let full = match settings.match_mode {
-                    Match::Prefix => format!("{}{}", pfx(settings.addr_type), v),
-                    _ => v.clone(),
-                };
-                Some(Target { raw: v, full })
-            } else {
-                println!("    跳过 '{}': 包含无效字符", s);
-                None
+    // 展开的形近写法: 原目标及其在 targets 中的位置
+    let mut groups: Vec<(String, Range<usize>)> = vec![];
+    // 目标来自文件时，搜索中监视该文件
+    let file = raw.strip_prefix('@').map(|p| p.trim().to_string());
+    let targets: Vec<Target> = if let Some(path) = raw.strip_prefix('@') {
+        let l = match list::load(path.trim(), settings) {
+            Ok(l) => l,
+            Err(e) => {
+                println!("\n  无法读取文件: {}", e);
+                pause();
+                return;
             }
-        })
-        .collect();
+        };
+        for (n, e) in l.errors.iter().take(10) {
+            println!("    跳过第 {} 行: {}", n, e);
+        }
+        if l.errors.len() > 10 {
+            println!("    ... 另有 {} 行无效", l.errors.len() - 10);
+        }
+        if !l.errors.is_empty() || l.dups > 0 {
+            println!("\n    读取 {} 个目标，无效 {} 行，重复 {} 行", l.specs.len(), l.errors.len(), l.dups);
+            pause();
+        }
+        l.specs.iter().filter_map(|s| Target::from_spec(s, settings)).collect()
+    } else {
+        let parts: Vec<&str> = if pattern { raw.split_whitespace().collect() } else { raw.split(',').collect() };
+        let mut bad = false;
+        let (a, nocase) = (settings.addr_type, settings.ignore_case);
+        // 有无效字符但能换成形近字的目标及其全部写法
+        let mut fixable: Vec<(String, Vec<String>)> = vec![];
+        let mut targets: Vec<Target> = parts.into_iter()
+            .filter(|s| !s.trim().is_empty())
+            .filter_map(|s| match Target::parse(&Spec::new(s), settings) {
+                Ok(t) => Some(t),
+                Err(e) => {
+                    println!("    跳过: {}", e);
+                    let s = s.trim();
+                    if !pattern && leet::needed(s, a, nocase) {
+                        match leet::suggest(s, a, nocase) {
+                            Some((v, swaps)) => println!("          可改为 {} ({})", v, swaps.join(", ")),
+                            None => println!("          有字符找不到形近的替换"),
+                        }
+                        if let Some(vs) = leet::variants(s, a, nocase) { fixable.push((s.to_string(), vs)); }
+                    }
+                    bad = true;
+                    None
+                }
+            })
+            .collect();
+        if !fixable.is_empty() {
+            let n: usize = fixable.iter().map(|(_, vs)| vs.len()).sum();
+            let yes = input(&format!("\n  展开为全部 {} 种可用写法一起搜索? (y/N): ", n));
+            if yes.eq_ignore_ascii_case("y") {
+                for (orig, vs) in fixable {
+                    let start = targets.len();
+                    targets.extend(vs.iter().filter_map(|v| Target::parse(&Spec::new(v.as_str()), settings).ok()));
+                    if targets.len() > start { groups.push((orig, start..targets.len())); }
+                }
+            }
+        } else if bad {
+            // 留出时间看清被跳过的原因
+            pause();
+        }
+        targets
+    };
 
     if targets.is_empty() {
         println!("\n  无有效目标!");
         pause();
         return;
     }
+    confirm(settings, targets, None, groups, file);
+}
+
+/// 词典搜索: 读入词表，地址中出现任一足够长的词即命中，结果按词长与罕见度排序
+fn dictionary(settings: &Settings) {
+    clear();
+    println!();
+    println!("  ╭─────────────────────────────────────────╮");
+    println!("  │             词典搜索                    │");
+    println!("  ╰─────────────────────────────────────────╯");
+    println!();
+    println!("    不指定目标，地址开头或数据部分出现词表中任一词即算命中");
+    println!("    词表每行一个词 (英文、拼音、品牌名等)，# 为注释");
+    println!("    字符集:   {}", charset(settings.addr_type));
+    if !is_bech32(settings.addr_type) && !settings.ignore_case {
+        println!("    注意:     Base58 区分大小写且没有 0 O I l，可在设置中改为忽略大小写");
+    }
+    println!();
+    println!("  留空按 Enter 返回");
+    println!();
+
+    let path = input("  词表文件: ");
+    if path.is_empty() { return; }
+    let min = input(&format!("  最短词长 [{}]: ", dict::MIN_LEN)).parse().ok().filter(|&n| n >= 1).unwrap_or(dict::MIN_LEN);
+    let w = match dict::load(path.trim_start_matches('@'), min, settings) {
+        Ok(w) => w,
+        Err(e) => {
+            println!("\n  无法读取文件: {}", e);
+            pause();
+            return;
+        }
+    };
+    println!("\n    可用 {} 个词 (过短 {}，不符合字符集 {}，重复 {})", fmt_num(w.words.len() as u64), w.short, w.invalid, w.dups);
+    if w.words.is_empty() {
+        println!("\n  无可用的词!");
+        pause();
+        return;
+    }
+    println!("    最长: {}", w.words.iter().take(5).cloned().collect::<Vec<_>>().join(", "));
+    // 首个命中就停的话没得挑，默认多收集几个
+    let n = input("\n  收集数量 [10]: ").parse().ok().filter(|&n| n >= 1).unwrap_or(10);
+    let settings = Settings { collect: Collect::Total(n), ..settings.clone() };
+    let targets = w.specs().iter().filter_map(|s| Target::from_spec(s, &settings)).collect();
+    confirm(&settings, targets, Some(min), vec![], None);
+}
 
+/// 美观评分: 不设目标，连续相同字符、循环、回文、顺序等特征的总分达到门槛即命中
+fn pretty(settings: &Settings) {
     clear();
     println!();
     println!("  ╭─────────────────────────────────────────╮");
+    println!("  │             美观评分                    │");
+    println!("  ╰─────────────────────────────────────────╯");
+    println!();
+    println!("    不指定目标，按 {} 之后的字符打分:", pfx(settings.addr_type, settings.network));
+    println!("      连续相同字符  如 qqqqq、AAAA");
+    println!("      片段循环      如 abab、123123");
+    println!("      回文          如 x7a9a7x");
+    println!("      顺序          如 2345、fedc");
+    println!("    单项分数约等于同样难得的靓号位数，开头和结尾的特征分更高");
+    println!("    各项互不重叠地累加，结果附带每一项的说明");
+    println!();
+    println!("  留空按 Enter 使用默认值  |  Esc 返回");
+    println!();
+
+    let min: f64 = input("  最低分数 [5]: ").parse().ok().filter(|&v: &f64| v > 0.0 && v.is_finite()).unwrap_or(5.0);
+    let n = input("  收集数量 [10]: ").parse().ok().filter(|&n| n >= 1).unwrap_or(10);
+    let settings = Settings { collect: Collect::Total(n), min_score: Some(min), ..settings.clone() };
+    confirm(&settings, vec![], None, vec![], None);
+}
+
+/// 确认配置后开始搜索；`words` 为词典模式的最短词长，`groups` 为展开了形近写法的目标，`file` 为目标列表文件。
+/// 开启评分时目标可以为空
+fn confirm(settings: &Settings, targets: Vec<Target>, words: Option<usize>, groups: Vec<(String, Range<usize>)>, file: Option<String>) {
+    clear();
+    println!();
+    // 评分门槛排在各目标之后
+    let exps: Vec<u64> = targets.iter().map(|t| t.expected(settings))
+        .chain(settings.min_score.map(|m| score::expected(m, settings))).collect();
+    let odds = Odds::new(exps.iter().copied());
+    println!("  ╭─────────────────────────────────────────╮");
     println!("  │             确认配置                    │");
     println!("  ╰─────────────────────────────────────────╯");
     println!();
-    println!("    目标:");
-    for t in &targets {
-        let show = match settings.match_mode {
-            Match::Prefix => format!("{}...", t.full),
-            Match::Suffix => format!("...{}", t.raw),
-            Match::Contains => format!("...{}...", t.raw),
-        };
-        let e = exp(t.raw.len(), settings.addr_type);
-        println!("      {} ({}位, 期望{}次)", show, t.raw.len(), fmt_num(e));
+    if let (Some(min), true) = (words, targets.len() > 10) {
+        let sm = list::summarize(&targets, settings);
+        println!("    词典:     {} 个词，{}-{} 位 (最短 {} 位)", fmt_num(sm.total as u64), sm.min_len, sm.max_len, min);
+        if let Some((t, e)) = sm.easiest {
+            println!("      最容易: {} (期望{}次)", t, fmt_num(e));
+        }
+    } else if targets.len() > 10 {
+        // 目标很多时只显示摘要
+        let sm = list::summarize(&targets, settings);
+        println!("    目标:     {} 个", fmt_num(sm.total as u64));
+        println!("      前缀 {} / 后缀 {} / 包含 {} / 模式 {} / 两端 {}",
+            sm.by_mode[0], sm.by_mode[1], sm.by_mode[2], sm.by_mode[3], sm.by_mode[4]);
+        println!("      长度 {}-{} 位{}", sm.min_len, sm.max_len,
+            if sm.max_priority > 0 { format!(", 最高优先级 {}", sm.max_priority) } else { String::new() });
+        if let Some((t, e)) = sm.easiest {
+            println!("      最容易: {} (期望{}次)", t, fmt_num(e));
+        }
+    } else if !targets.is_empty() {
+        println!("    目标:");
+        for (i, t) in targets.iter().enumerate() {
+            let show = match t.mode {
+                Match::Prefix => format!("{}...", t.full(settings)),
+                Match::Suffix => format!("...{}", t.raw),
+                Match::Contains => format!("...{}...", t.raw),
+                Match::Pattern => format!("/{}/", t.raw),
+                Match::Both => t.full(settings),
+            };
+            let e = exps[i];
+            // 词典模式的优先级就是词长
+            let pri = if t.priority > 0 && words.is_none() { format!(", 优先级{}", t.priority) } else { String::new() };
+            // 首个结果落在该目标上的概率
+            let share = if targets.len() > 1 { format!(", 占{:.1}%", odds.target(i, 0).0 * 100.0) } else { String::new() };
+            println!("      {} ({}位, 期望{}次{}{})", show, t.fixed_len(), fmt_num(e), share, pri);
+        }
+    }
+    // 同一目标的各种写法合起来的难度
+    for (orig, r) in &groups {
+        let vs: Vec<&str> = targets[r.clone()].iter().take(6).map(|t| t.raw.as_str()).collect();
+        let more = if r.len() > vs.len() { format!(" 等 {} 种", r.len()) } else { String::new() };
+        let set = Odds::new(exps[r.clone()].iter().copied());
+        println!("    变体:     {} → {}{}", orig, vs.join(", "), more);
+        println!("      {} 种写法合计期望 {} 次 (最容易的单个写法 {} 次)", r.len(), fmt_num(set.expected().round() as u64),
+            fmt_num(exps[r.clone()].iter().copied().min().unwrap_or(0)));
+    }
+    if let Some(m) = settings.min_score {
+        println!("    评分:     ≥ {} 分 (期望约 {} 次，粗略估计)", m, fmt_num(score::expected(m, settings)));
+    }
+    if exps.len() > 1 {
+        println!("      合计: 命中任一目标期望 {} 次", fmt_num(odds.expected().round() as u64));
+    }
+    // 同一组目标之前搜过的话接着累计
+    let ledger = Ledger::open(&targets, settings);
+    if ledger.resumed() {
+        let w = ledger.prior;
+        println!("      累计: 已搜索 {} 次，共尝试 {} 次，耗时 {}，应已命中的概率 {:.1}%",
+            w.runs, fmt_num(w.attempts), fmt_time(w.secs as u64), odds.cumulative(w.since) * 100.0);
     }
     println!();
-    println!("    地址类型: {}", addr_name(settings.addr_type));
-    println!("    匹配模式: {}", match_name(settings.match_mode));
+    println!("    地址类型: {}", addr_name(settings.addr_type, settings.network));
+    if settings.ignore_case && !is_bech32(settings.addr_type) {
+        println!("    大小写:   忽略");
+    }
+    if !is_main(settings.network) {
+        println!("    网络:     {}", net_name(settings.network));
+    }
+    if targets.iter().any(|t| matches!(t.mode, Match::Suffix | Match::Contains | Match::Both)) {
+        println!("    匹配区域: {}", region_name(settings));
+    }
+    if words.is_some() {
+        println!("    匹配模式: 词典 (包含任一词，按词长与罕见度排序)");
+    } else if targets.is_empty() {
+        println!("    匹配模式: 美观评分 (按分数排序)");
+    } else if targets.iter().all(|t| t.mode == settings.match_mode) {
+        println!("    匹配模式: {}", match_name(settings.match_mode));
+    } else {
+        println!("    匹配模式: 按目标单独指定");
+    }
     println!("    输出格式: {}", out_name(settings.output));
     println!("    随机源:   {}", rng_name(settings.rng_mode));
+    println!("    收集数量: {}", collect_name(settings.collect));
+    if settings.max_time.is_some() || settings.max_attempts.is_some() || settings.max_prob.is_some() {
+        println!("    停止条件: {}", limits_name(settings));
+    }
+
+    if let Some(f) = &file {
+        println!("    目标文件: {} (搜索中修改会自动重新读取)", f);
+    }
 
     if settings.output == Out::Wif {
         println!();
@@ -442,222 +720,398 @@ fn generate(settings: &Settings) {
         _ => {}
     }
 
-    run_search(settings.clone(), targets);
+    run_search(settings.clone(), targets, exps, ledger, words.is_some(), file);
 }
 
-fn run_search(settings: Settings, targets: Vec<Target>) {
-    let stop = Arc::new(AtomicBool::new(false));
-    let cnt = Arc::new(AtomicU64::new(0));
-    let t0 = Instant::now();
-    let (tx, rx) = mpsc::channel::<Found>();
-
-    let min_exp: u64 = targets.iter().map(|t| exp(t.raw.len(), settings.addr_type)).min().unwrap_or(1);
+/// 多个目标时列出最容易的几个及其各自的概率；评分门槛不是目标，不列出
+fn easiest(targets: &[Target], exps: &[u64], s: &Settings) -> (Vec<usize>, Vec<String>) {
+    let mut top: Vec<usize> = (0..targets.len()).collect();
+    top.sort_by_key(|&i| exps[i]);
+    top.truncate(if exps.len() > 1 { 3 } else { 0 });
+    let names = top.iter().map(|&i| targets[i].full(s).chars().take(16).collect()).collect();
+    (top, names)
+}
 
-    clear();
-    println!();
-    println!("  ╭─────────────────────────────────────────╮");
-    println!("  │             搜索中...                   │");
-    println!("  ╰─────────────────────────────────────────╯");
-    println!();
-    println!();
-    println!();
-    println!();
-    println!();
+/// 结果列表的起始行: 进度区、最容易的目标和近似命中榜（一个标题行和 NEAR_ROWS 行）之后
+fn rows(resumed: bool, top: usize, near: bool) -> usize {
+    14 + usize::from(resumed) + if top == 0 { 0 } else { top + 1 } + if near { NEAR_ROWS + 2 } else { 0 }
+}
 
-    let p_stop = stop.clone();
-    let p_cnt = cnt.clone();
-    let prog = thread::spawn(move || {
-        let mut last = 0u64;
-        let bar_width = 35;
+/// 搜索中修改目标: `+abc` 或 `abc` 添加，`-abc` 删除，模式用空格分隔，其余用逗号。返回修改后的全部目标，没有改动时返回 None
+fn edit_targets(cur: Vec<Target>, line: &str, s: &Settings, dict: bool) -> Option<Vec<Spec>> {
+    let parts: Vec<&str> = if s.match_mode == Match::Pattern { line.split_whitespace().collect() } else { line.split(',').collect() };
+    let mut specs: Vec<Spec> = cur.into_iter().map(Spec::from).collect();
+    let mut changed = false;
+    for p in parts.into_iter().map(str::trim).filter(|p| !p.is_empty()) {
+        let (del, text) = match p.strip_prefix('-') { Some(t) => (true, t), None => (false, p.strip_prefix('+').unwrap_or(p)) };
+        // 词典模式的目标都是包含匹配，优先级为词长
+        let spec = if dict { Spec { text: text.into(), mode: Some(Match::Contains), priority: text.chars().count() as u32 } } else { Spec::new(text) };
+        if del {
+            // 按规范化后的写法比较，忽略大小写时 `-ABC` 也能删掉 `abc`
+            let raw = Target::parse(&spec, s).map_or(text.to_string(), |t| t.raw);
+            let n = specs.len();
+            specs.retain(|x| x.text != raw);
+            changed |= specs.len() < n;
+        } else {
+            specs.push(spec);
+            changed = true;
+        }
+    }
+    changed.then_some(specs)
+}
 
-        loop {
-            thread::sleep(Duration::from_millis(200));
-            if p_stop.load(Ordering::Relaxed) { break; }
+/// `exps` 为各目标的期望次数，合并成总命中率；进度、ETA 与运气都由它计算。
+/// 概率与运气包含 `ledger` 中之前搜索同一组目标的累计量；`dict` 为词典模式，结果按词长与罕见度排序。
+/// 搜索中按 t 增删目标，或 `file` 被修改时重新读取；换了目标后从账本取新目标的累计量，重算概率与 ETA
+fn run_search(settings: Settings, targets: Vec<Target>, mut exps: Vec<u64>, mut ledger: Ledger, dict: bool, file: Option<String>) {
+    let mut odds = Odds::new(exps.iter().copied());
+    let (mut top, mut names) = easiest(&targets, &exps, &settings);
+    let mut resumed = ledger.resumed();
+    let max_threads = num_cpus::get();
+    let mut watch = file.map(list::Watch::new);
+
+    let mut handle = match Searcher::with_settings(settings.clone())
+        .specs(targets.into_iter().map(Spec::from))
+        .prior(ledger.prior.since)
+        .spawn()
+    {
+        Ok(h) => h,
+        Err(e) => {
+            println!("\n  {}", e);
+            pause();
+            return;
+        }
+    };
 
-            let cur = p_cnt.load(Ordering::Relaxed);
-            let spd = (cur - last) * 5;
-            last = cur;
+    let mut list_row = rows(resumed, top.len(), handle.tracks_near());
+    // 搜索中按编号标记保留的近似命中，结束后连同密钥一起显示
+    let mut kept: Vec<Partial> = vec![];
+    let mut board: Vec<Partial> = vec![];
+    // 换目标或读取目标文件的结果，显示在按键提示下面
+    let mut msg = String::new();
+    let mut redraw = true;
+
+    // 搜索期间开启 raw 模式读取按键，各行按绝对位置输出；Ctrl-C 也作为按键处理，终端不会留在 raw 模式
+    terminal::enable_raw_mode().ok();
+
+    let (mut last, mut last_t, mut spd) = (0u64, Instant::now(), 0u64);
+    let bar_width = 35;
+
+    let mut found: Vec<Found> = vec![];
+    // 上一次命中时的尝试次数，之后的概率与 ETA 针对下一个结果
+    let mut base = 0u64;
+    // 最近一次换目标时的尝试次数，之前的尝试针对的是旧目标
+    let mut from = 0u64;
+    // 定期写入账本，直接关掉终端也只丢最后一小段
+    let mut saved = Instant::now();
+    let mut quit = false;
+
+    while !quit {
+        // 换了目标后各区域行数可能变化，整屏重画
+        if redraw {
+            clear();
+            put(2, "  ╭─────────────────────────────────────────╮");
+            put(3, "  │             搜索中...                   │");
+            put(4, "  ╰─────────────────────────────────────────╯");
+            for (i, r) in found.iter().enumerate() { put(list_row + i, &found_line(i, r, dict)); }
+            redraw = false;
+        }
+        match handle.recv_timeout(Duration::from_millis(200)) {
+            Ok(r) => {
+                // 结果实时列出地址，密钥在结束后统一显示
+                put(list_row + found.len(), &found_line(found.len(), &r, dict));
+                found.push(r);
+                base = handle.attempts();
+            }
+            Err(RecvTimeoutError::Disconnected) => break,
+            Err(RecvTimeoutError::Timeout) => {}
+        }
 
-            let elapsed = t0.elapsed().as_secs();
-            let pct = (cur as f64 / min_exp as f64 * 100.0).min(100.0);
-            let luck = if cur > 0 { min_exp as f64 / cur as f64 } else { 0.0 };
+        // 新的全部目标
+        let mut next: Option<Vec<Spec>> = None;
+        // 目标文件中的无效行数
+        let mut bad = 0;
+        if let Some(w) = watch.as_mut() {
+            if w.changed() {
+                match list::load(&w.path, &settings) {
+                    Ok(l) => { bad = l.errors.len(); next = Some(l.specs); }
+                    Err(e) => msg = format!("无法读取目标文件: {}", e),
+                }
+            }
+        }
 
-            let luck_tag = if luck > 2.0 { "欧皇" } else if luck > 1.0 { "好运" } else if luck > 0.5 { "正常" } else { "非酋" };
+        while event::poll(Duration::ZERO).unwrap_or(false) {
+            let Ok(Event::Key(k)) = event::read() else { continue };
+            if k.kind != KeyEventKind::Press { continue; }
+            match k.code {
+                KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('Q') => quit = true,
+                KeyCode::Char('c') if k.modifiers.contains(KeyModifiers::CONTROL) => quit = true,
+                KeyCode::Char('p') | KeyCode::Char('P') | KeyCode::Char(' ') => handle.set_paused(!handle.is_paused()),
+                KeyCode::Char('+') | KeyCode::Char('=') => {
+                    let n = handle.threads();
+                    if n < max_threads { handle.set_threads(n + 1); }
+                }
+                KeyCode::Char('-') | KeyCode::Char('_') => {
+                    let n = handle.threads();
+                    if n > 1 { handle.set_threads(n - 1); }
+                }
+                KeyCode::Char(c @ '1'..='9') => {
+                    if let Some(p) = board.get(c as usize - '1' as usize) { keep(&mut kept, p); }
+                }
+                KeyCode::Char('t') | KeyCode::Char('T') => {
+                    // 输入期间搜索照常进行
+                    print!("\x1B[{};1H\x1B[J", list_row + found.len() + 1);
+                    let line = input("    目标 (+abc 添加, -abc 删除, 多个用逗号分隔): ");
+                    terminal::enable_raw_mode().ok();
+                    next = edit_targets(handle.targets(), &line, &settings, dict).or(next);
+                    redraw = true;
+                }
+                _ => {}
+            }
+        }
 
-            let eta = if spd > 0 && cur < min_exp {
-                fmt_time((min_exp - cur) / spd)
-            } else if cur >= min_exp { "随时".into() } else { "...".into() };
+        if let Some(specs) = next {
+            let (cur, secs, got) = (handle.attempts(), handle.elapsed().as_secs_f64(), found.len() as u64);
+            let ts = Searcher::with_settings(settings.clone()).specs(specs).resolve();
+            // 旧目标的计数记到换目标为止，新目标从现在开始记
+            let switched = ts.and_then(|ts| {
+                let l = Ledger::open(&ts, &settings).starting_at(cur, secs, got);
+                handle.set_targets(ts.iter().cloned().map(Spec::from), l.prior.since)?;
+                Ok((ts, l))
+            });
+            match switched {
+                Ok((ts, l)) => {
+                    ledger.record(cur, secs, got, (!found.is_empty()).then_some(base)).ok();
+                    ledger = l;
+                    from = cur;
+                    resumed = ledger.resumed();
+                    exps = ts.iter().map(|t| t.expected(&settings))
+                        .chain(settings.min_score.map(|m| score::expected(m, &settings))).collect();
+                    odds = Odds::new(exps.iter().copied());
+                    (top, names) = easiest(&ts, &exps, &settings);
+                    list_row = rows(resumed, top.len(), handle.tracks_near());
+                    msg = format!("目标已更新: {} 个，合计期望 {} 次", ts.len(), fmt_num(odds.expected().round() as u64));
+                    if bad > 0 { msg += &format!("，跳过无效 {} 行", bad); }
+                    redraw = true;
+                }
+                Err(e) => msg = format!("目标未更新: {}", e),
+            }
+        }
 
-            print!("\x1B[6;1H");
-            println!("    {} {:>5.1}%", progress_bar(pct, bar_width), pct);
-            println!();
-            println!("    速度: {:>12}/s    已尝试: {:>15}", fmt_num(spd), fmt_num(cur));
-            println!("    运气: {:>12}      ETA: {:>15}", luck_tag, eta);
-            println!("    耗时: {:>12}", fmt_time(elapsed));
-            io::stdout().flush().ok();
+        let cur = handle.attempts();
+        // 按实际间隔计算速度，至少隔 1 秒更新一次以免跳动
+        let dt = last_t.elapsed().as_secs_f64();
+        if dt >= 1.0 {
+            spd = ((cur - last) as f64 / dt) as u64;
+            (last, last_t) = (cur, Instant::now());
+        }
+        let hit = (!found.is_empty()).then_some(base);
+        let life = ledger.lifetime(cur, handle.elapsed().as_secs_f64(), found.len() as u64, hit);
+        let n = life.since;
+        if saved.elapsed() >= Duration::from_secs(10) {
+            ledger.record(cur, handle.elapsed().as_secs_f64(), found.len() as u64, hit).ok();
+            saved = Instant::now();
         }
-    });
-
-    let mut hs = vec![];
-    let targets = Arc::new(targets);
-
-    for _ in 0..settings.threads {
-        let settings = settings.clone();
-        let targets = targets.clone();
-        let stop = stop.clone();
-        let cnt = cnt.clone();
-        let tx = tx.clone();
-
-        hs.push(thread::spawn(move || {
-            let secp = Secp256k1::new();
-            let path = DerivationPath::from_str(deriv(settings.addr_type)).unwrap();
-            let mut buf = String::with_capacity(64);
-            let mut local = 0u64;
-
-            // 根据设置选择 RNG
-            let mut secure_rng = OsRng;
-            let mut fast_rng = Xoshiro256PlusPlus::from_entropy();
-
-            // WIF-only 模式: 直接生成随机私钥，跳过 BIP39/BIP32
-            let wif_only = settings.output == Out::Wif;
-
-            loop {
-                if stop.load(Ordering::Relaxed) { break; }
-
-                for _ in 0..settings.batch_size {
-                    let (addr, mnemonic_str, secret_key) = if wif_only {
-                        // 快速模式: 直接生成随机私钥
-                        let mut key_bytes = [0u8; 32];
-                        match settings.rng_mode {
-                            RngMode::Secure => secure_rng.fill_bytes(&mut key_bytes),
-                            RngMode::Fast => fast_rng.fill_bytes(&mut key_bytes),
-                        }
 
-                        let sk = match SecretKey::from_slice(&key_bytes) {
-                            Ok(k) => k,
-                            Err(_) => continue,
-                        };
-
-                        let addr = match settings.addr_type {
-                            Addr::Taproot => {
-                                let kp = sk.keypair(&secp);
-                                let (x, _) = XOnlyPublicKey::from_keypair(&kp);
-                                Address::p2tr(&secp, x, None, Network::Bitcoin)
-                            }
-                            Addr::SegWit => {
-                                let pk = PublicKey::new(sk.public_key(&secp));
-                                match Address::p2wpkh(&pk, Network::Bitcoin) {
-                                    Ok(a) => a,
-                                    Err(_) => continue,
-                                }
-                            }
-                            Addr::Legacy => {
-                                let pk = PublicKey::new(sk.public_key(&secp));
-                                Address::p2pkh(&pk, Network::Bitcoin)
-                            }
-                            Addr::P2SH => {
-                                let pk = PublicKey::new(sk.public_key(&secp));
-                                match Address::p2shwpkh(&pk, Network::Bitcoin) {
-                                    Ok(a) => a,
-                                    Err(_) => continue,
-                                }
-                            }
-                        };
-                        (addr, None, sk)
-                    } else {
-                        // 标准模式: BIP39 助记词 -> BIP32 派生
-                        let mut ent = [0u8; 32];
-                        match settings.rng_mode {
-                            RngMode::Secure => secure_rng.fill_bytes(&mut ent),
-                            RngMode::Fast => fast_rng.fill_bytes(&mut ent),
-                        }
+        let elapsed = handle.elapsed().as_secs();
+        let p = odds.cumulative(n);
+        let paused = handle.is_paused();
 
-                        let mn = match Mnemonic::from_entropy(&ent) { Ok(m) => m, Err(_) => continue };
-                        let seed = mn.to_seed("");
-                        let root = match ExtendedPrivKey::new_master(Network::Bitcoin, &seed) { Ok(r) => r, Err(_) => continue };
-                        let child = match root.derive_priv(&secp, &path) { Ok(k) => k, Err(_) => continue };
-
-                        let addr = match settings.addr_type {
-                            Addr::Taproot => {
-                                let kp = child.to_keypair(&secp);
-                                let (x, _) = XOnlyPublicKey::from_keypair(&kp);
-                                Address::p2tr(&secp, x, None, Network::Bitcoin)
-                            }
-                            Addr::SegWit => {
-                                let pk = PublicKey::new(child.to_keypair(&secp).public_key());
-                                match Address::p2wpkh(&pk, Network::Bitcoin) {
-                                    Ok(a) => a,
-                                    Err(_) => continue,
-                                }
-                            }
-                            Addr::Legacy => {
-                                let pk = PublicKey::new(child.to_keypair(&secp).public_key());
-                                Address::p2pkh(&pk, Network::Bitcoin)
-                            }
-                            Addr::P2SH => {
-                                let pk = PublicKey::new(child.to_keypair(&secp).public_key());
-                                match Address::p2shwpkh(&pk, Network::Bitcoin) {
-                                    Ok(a) => a,
-                                    Err(_) => continue,
-                                }
-                            }
-                        };
-                        (addr, Some(mn.to_string()), child.private_key)
-                    };
-
-                    buf.clear();
-                    write!(&mut buf, "{}", addr).unwrap();
-                    local += 1;
-
-                    for t in targets.iter() {
-                        let hit = match settings.match_mode {
-                            Match::Prefix => buf.starts_with(&t.full),
-                            Match::Suffix => buf.ends_with(&t.full),
-                            Match::Contains => buf.contains(&t.full),
-                        };
-                        if hit {
-                            cnt.fetch_add(local, Ordering::Relaxed);
-                            if !stop.swap(true, Ordering::Relaxed) {
-                                let wif = if settings.output == Out::Wif || settings.output == Out::Both {
-                                    Some(PrivateKey::new(secret_key, Network::Bitcoin).to_wif())
-                                } else { None };
-                                let _ = tx.send(Found {
-                                    addr: buf.clone(),
-                                    mnemonic: if settings.output == Out::Mnemonic || settings.output == Out::Both {
-                                        mnemonic_str.clone()
-                                    } else { None },
-                                    wif,
-                                    target: t.raw.clone(),
-                                });
-                            }
-                            return;
-                        }
-                    }
-                }
+        // 已有结果时比较实际与期望的命中数，否则看 "到现在还没中" 有多少见
+        let luck_tag = if life.found > 0 {
+            let ratio = life.found as f64 / (life.attempts as f64 * odds.rate());
+            if ratio > 2.0 { "欧皇" } else if ratio > 1.0 { "好运" } else if ratio > 0.5 { "正常" } else { "非酋" }
+        } else if p < 0.5 { "正常" } else if p < 0.9 { "偏慢" } else if p < 0.99 { "非酋" } else { "极非" };
 
-                if local >= 1000 {
-                    cnt.fetch_add(local, Ordering::Relaxed);
-                    local = 0;
-                }
+        let eta = |q: f64| {
+            let need = odds.attempts_for(q) - n as f64;
+            if need <= 0.0 { "已过".to_string() } else if spd > 0 && !paused { fmt_time((need / spd as f64) as u64) } else { "...".into() }
+        };
+
+        let mut row = 6;
+        let mut line = |s: String| { put(row, &s); row += 1; };
+        line(format!("    {} {:>5.1}%  应已命中的概率", progress_bar(p * 100.0, bar_width), p * 100.0));
+        line(String::new());
+        line(format!("    速度: {:>12}/s    已尝试: {:>15}", fmt_num(if paused { 0 } else { spd }), fmt_num(cur)));
+        line(format!("    运气: {:>12}      耗时:   {:>15}", luck_tag, fmt_time(elapsed)));
+        line(format!("    ETA:  50% {:>10}   90% {:>10}   99% {:>10}", eta(0.5), eta(0.9), eta(0.99)));
+        if resumed {
+            line(format!("    累计: {:>12} 次    第 {} 次搜索，共耗时 {}", fmt_num(life.attempts), life.runs, fmt_time(life.secs as u64)));
+        }
+        line(format!("    线程: {:>2}/{:<2} {}   p 暂停/继续  +/- 线程  t 增删目标  Esc/q 停止",
+            handle.threads(), max_threads, if paused { "[已暂停]" } else { "        " }));
+        line(format!("    {}", msg));
+        if !top.is_empty() {
+            line(String::new());
+            for (&i, name) in top.iter().zip(&names) {
+                let (share, pi) = odds.target(i, n);
+                // 该目标目前最多匹配了几位
+                let reach = if handle.tracks_near() { format!("  最多 {} 位", handle.reach(i)) } else { String::new() };
+                line(format!("    {:<16} 期望 {:>14} 次  占 {:>5.1}%  已 {:>5.1}%{}", name, fmt_num(exps[i]), share * 100.0, pi * 100.0, reach));
+            }
+        }
+        if handle.tracks_near() {
+            // 榜上只显示地址，密钥要在结束后选择保留才显示
+            board = handle.board(NEAR_ROWS);
+            line(String::new());
+            line("    近似命中榜 (按 1-5 标记保留，结束后显示密钥)".into());
+            for i in 0..NEAR_ROWS {
+                line(board.get(i).map_or(String::new(), |p| near_line(i, p, &kept)));
             }
-            cnt.fetch_add(local, Ordering::Relaxed);
-        }));
+        }
+        io::stdout().flush().ok();
     }
 
-    drop(tx);
+    // 手动停止时取出线程退出前已发出的结果
+    handle.stop();
+    while let Some(r) = handle.recv() {
+        put(list_row + found.len(), &found_line(found.len(), &r, dict));
+        found.push(r);
+        base = handle.attempts();
+    }
+    let dur = handle.elapsed();
+    let tot = handle.attempts();
+    let (limit, best) = (handle.limit(), handle.best());
+    let board = handle.board(NEAR_REVIEW);
+    handle.join();
+    terminal::disable_raw_mode().ok();
+    if !board.is_empty() { review_near(&board, &mut kept); }
+    let hit = (!found.is_empty()).then_some(base);
+    ledger.record(tot, dur.as_secs_f64(), found.len() as u64, hit).ok();
+    let life = resumed.then(|| ledger.lifetime(tot, dur.as_secs_f64(), found.len() as u64, hit));
+    if !found.is_empty() {
+        // 词典模式把最长、最罕见的词排在前面
+        let scores: Vec<dict::Score> = if dict {
+            let ranked = dict::rank(&found, &settings);
+            found = ranked.iter().map(|(i, _)| found[*i].clone()).collect();
+            ranked.into_iter().map(|(_, s)| s).collect()
+        } else { vec![] };
+        // 评分命中按分数从高到低，命中目标的结果排在前面
+        if settings.min_score.is_some() {
+            found.sort_by(|a, b| b.score.as_ref().map_or(f64::INFINITY, |s| s.total)
+                .total_cmp(&a.score.as_ref().map_or(f64::INFINITY, |s| s.total)));
+        }
+        show_found(&odds, &found, &scores, dur, tot, life, &kept);
+    } else {
+        show_stopped(&odds, dur, tot, tot - from, life, limit, best, &kept);
+    }
+}
 
-    if let Ok(r) = rx.recv() {
-        let dur = t0.elapsed();
-        let tot = cnt.load(Ordering::Relaxed);
-        let e = exp(r.target.len(), settings.addr_type);
-        let luck = e as f64 / tot as f64;
+/// 搜索中实时列出的结果: 只有地址，词典模式带上命中的词，评分命中带上分数
+fn found_line(i: usize, r: &Found, dict: bool) -> String {
+    match &r.score {
+        Some(s) => format!("    [{}] {}  {:.1} 分", i + 1, r.addr, s.total),
+        None if dict => format!("    [{}] {}  {}", i + 1, r.addr, r.target),
+        None => format!("    [{}] {}", i + 1, r.addr),
+    }
+}
 
+/// 搜索中显示的近似命中条数，与结束后可选择的条数
+const NEAR_ROWS: usize = 5;
+const NEAR_REVIEW: usize = 10;
+
+/// 保留或取消保留一条近似命中（按地址区分）
+fn keep(kept: &mut Vec<Partial>, p: &Partial) {
+    match kept.iter().position(|k| k.found.addr == p.found.addr && k.found.target == p.found.target) {
+        Some(i) => { kept.remove(i); }
+        None => kept.push(p.clone()),
+    }
+}
+
+/// 近似命中榜的一行: 编号、是否保留、地址与匹配位数
+fn near_line(i: usize, p: &Partial, kept: &[Partial]) -> String {
+    let mark = if kept.iter().any(|k| k.found.addr == p.found.addr && k.found.target == p.found.target) { "*" } else { " " };
+    let t: String = p.found.target.chars().take(16).collect();
+    format!("    [{}]{} {}  {}/{} {}", i + 1, mark, p.found.addr, p.matched, p.len, t)
+}
+
+/// 结束后列出近似命中榜，输入编号保留，保留的会连同密钥显示在结果里
+fn review_near(board: &[Partial], kept: &mut Vec<Partial>) {
+    loop {
         clear();
         println!();
         println!("  ╭─────────────────────────────────────────╮");
-        println!("  │            * 找到了! *                  │");
+        println!("  │             近似命中榜                  │");
         println!("  ╰─────────────────────────────────────────╯");
         println!();
+        for (i, p) in board.iter().enumerate() {
+            println!("{}", near_line(i, p, kept));
+        }
+        println!();
+        println!("    * 为已保留，保留的地址会连同密钥一起显示");
+        let s = input("\n  输入编号切换保留 (如 1,3)，直接 Enter 继续: ");
+        if s.is_empty() { break; }
+        for n in s.split([',', ' ']).filter_map(|x| x.trim().parse::<usize>().ok()) {
+            if let Some(p) = board.get(n.wrapping_sub(1)) { keep(kept, p); }
+        }
+    }
+}
+
+/// 保留的近似命中及其密钥
+fn show_kept(kept: &[Partial]) {
+    if kept.is_empty() { return; }
+    println!();
+    println!("  ── 保留的近似命中 ───────────────────────────");
+    for p in kept {
+        println!();
+        println!("    地址: {}  (目标 {} 匹配 {}/{} 位)", p.found.addr, p.found.target, p.matched, p.len);
+        if let Some(ref m) = p.found.mnemonic { println!("    助记词: {}", m); }
+        if let Some(ref w) = p.found.wif { println!("    私钥 (WIF): {}", w); }
+        if let Some(ref d) = p.found.path { println!("    派生路径: {}", d); }
+    }
+}
+
+/// 手动停止或达到上限且没有结果时的汇总；`best` 只显示地址
+/// `since` 为最近一次换目标以来的尝试次数，用于计算当前目标的命中概率
+#[allow(clippy::too_many_arguments)]
+fn show_stopped(odds: &Odds, dur: Duration, tot: u64, since: u64, life: Option<Work>, limit: Option<Limit>, best: Option<Partial>, kept: &[Partial]) {
+    clear();
+    println!();
+    println!("  ╭─────────────────────────────────────────╮");
+    println!("  │             未找到                      │");
+    println!("  ╰─────────────────────────────────────────╯");
+    println!();
+    println!("    原因: {}", limit.map_or("手动停止", limit_name));
+    println!("    耗时: {:.2?}", dur);
+    println!("    尝试: {} 次", fmt_num(tot));
+    println!("    速度: {}/s", fmt_num((tot as f64 / dur.as_secs_f64().max(1e-9)) as u64));
+    println!("    本次应已命中的概率: {:.1}%", odds.cumulative(since) * 100.0);
+    if let Some(b) = best {
+        println!("    最接近: {}", b.found.addr);
+        println!("            目标 {} 匹配 {}/{} 位", b.found.target, b.matched, b.len);
+    }
+    if let Some(w) = life {
+        println!("    累计: {} 次搜索，尝试 {} 次，耗时 {}，应已命中的概率 {:.1}%",
+            w.runs, fmt_num(w.attempts), fmt_time(w.secs as u64), odds.cumulative(w.since) * 100.0);
+    }
+    println!();
+    println!("    尝试之间相互独立，下次搜索同一组目标会接着累计");
+    if !kept.is_empty() {
+        show_kept(kept);
+        println!();
+        println!("    !! 请立即安全保存以上密钥 !!");
+    }
+    pause();
+}
+
+/// `life` 为之前搜索过同一组目标时加上本次的累计量；`scores` 为词典模式下与 `found` 对应的评分
+fn show_found(odds: &Odds, found: &[Found], scores: &[dict::Score], dur: Duration, tot: u64, life: Option<Work>, kept: &[Partial]) {
+    // 合并命中率下得到这么多结果的期望次数
+    let e = found.len() as f64 * odds.expected();
+    let luck = e / tot as f64;
+
+    clear();
+    println!();
+    println!("  ╭─────────────────────────────────────────╮");
+    println!("  │            * 找到了! *                  │");
+    println!("  ╰─────────────────────────────────────────╯");
+
+    for (i, r) in found.iter().enumerate() {
+        println!();
+        if found.len() > 1 {
+            println!("  ── [{}/{}] ─────────────────────────────────", i + 1, found.len());
+            println!();
+        }
         println!("    地址:");
         println!("    {}", r.addr);
         println!();
@@ -674,22 +1128,38 @@ fn run_search(settings: Settings, targets: Vec<Target>) {
             println!();
         }
 
-        if r.mnemonic.is_some() {
-            println!("    派生路径: {}", deriv(settings.addr_type));
+        if let Some(ref p) = r.path {
+            println!("    派生路径: {}", p);
         }
-        println!("    匹配目标: {}", r.target);
-        println!();
-        println!("  ─────────────────────────────────────────");
-        println!("    耗时: {:.2?}", dur);
-        println!("    尝试: {} 次", fmt_num(tot));
-        println!("    运气: {:.2}x (期望 {} 次)", luck, fmt_num(e));
-        println!("  ─────────────────────────────────────────");
-        println!();
-        println!("    !! 请立即安全保存以上密钥 !!");
-
-        pause();
+        match scores.get(i) {
+            Some(sc) => println!("    命中词:   {} ({} 位，{}，罕见度 1/{})", sc.word, sc.len,
+                if sc.lead { "作为前缀".to_string() } else { format!("第 {} 位起", sc.at + 1) }, fmt_num(sc.rarity.round() as u64)),
+            None => match &r.score {
+                Some(s) => {
+                    println!("    美观评分: {:.1} 分", s.total);
+                    for e in s.explain() { println!("      {}", e); }
+                }
+                None => println!("    匹配目标: {}", r.target),
+            },
+        }
+    }
+    println!();
+    println!("  ─────────────────────────────────────────");
+    println!("    耗时: {:.2?}", dur);
+    println!("    尝试: {} 次", fmt_num(tot));
+    if found.len() > 1 {
+        println!("    找到: {} 个", found.len());
     }
+    println!("    运气: {:.2}x (期望 {} 次)", luck, fmt_num(e.round() as u64));
+    if let Some(w) = life {
+        let e = w.found as f64 * odds.expected();
+        println!("    累计: {} 次搜索，尝试 {} 次，耗时 {}，找到 {} 个，运气 {:.2}x",
+            w.runs, fmt_num(w.attempts), fmt_time(w.secs as u64), w.found, e / w.attempts as f64);
+    }
+    println!("  ─────────────────────────────────────────");
+    show_kept(kept);
+    println!();
+    println!("    !! 请立即安全保存以上密钥 !!");
 
-    for h in hs { let _ = h.join(); }
-    let _ = prog.join();
+    pause();
 }
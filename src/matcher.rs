@@ -0,0 +1,795 @@
+//! 目标预筛: 在地址原始数据（hash160 / 见证程序）上排除不可能命中的候选
+//!
+//! 预筛只负责"一定不命中"的判断，返回 true 的候选仍需编码成字符串确认。
+
+use crate::addr::{casings, checksum_len, exp, is_bech32, is_main, pfx, Addr, BASE58, BECH32};
+use crate::base58::{next_chars, prefix_prob, prefix_ranges, Range};
+use crate::config::{Match, Settings, Target, ANY};
+use crate::pattern::Pattern;
+use aho_corasick::AhoCorasick;
+use std::borrow::Cow;
+use std::ops::RangeInclusive;
+
+/// Bech32 校验和字符数
+const CHECKSUM: usize = 6;
+
+
+/// Base58Check 版本字节
+pub fn version(a: Addr, main: bool) -> u8 {
+    match (a, main) {
+        (Addr::P2SH, true) => 0x05,
+        (Addr::P2SH, false) => 0xc4,
+        (_, true) => 0x00,
+        (_, false) => 0x6f,
+    }
+}
+
+/// 目标作为前缀时可能的完整开头（测试网 Legacy 首字符为 m 或 n，忽略大小写时展开全部写法）；
+/// 写法过多时返回 None
+pub fn prefix_fulls(raw: &str, s: &Settings) -> Option<Vec<String>> {
+    let heads = match (s.addr_type, is_main(s.network)) {
+        (Addr::Legacy, false) => vec!["m", "n"],
+        (a, _) => vec![pfx(a, s.network)],
+    };
+    let bodies = if s.ignore_case && !is_bech32(s.addr_type) { spellings(raw)? } else { vec![raw.to_string()] };
+    Some(heads.iter().flat_map(|h| bodies.iter().map(move |b| format!("{}{}", h, b))).collect())
+}
+
+/// Base58 目标作为前缀的精确命中概率（不同开头与写法互不相交，直接相加）；
+/// Bech32 或写法过多时返回 None
+pub fn prefix_exact(raw: &str, s: &Settings) -> Option<f64> {
+    if is_bech32(s.addr_type) { return None; }
+    let ver = version(s.addr_type, is_main(s.network));
+    Some(prefix_fulls(raw, s)?.iter().map(|f| prefix_prob(f, ver)).sum())
+}
+
+/// 前缀不可能出现的原因: 版本字符之后第一位超出范围，或整体超出数值范围
+pub fn prefix_why(raw: &str, s: &Settings) -> String {
+    let ver = version(s.addr_type, is_main(s.network));
+    let heads = match (s.addr_type, is_main(s.network)) {
+        (Addr::Legacy, false) => vec!["m", "n"],
+        (a, _) => vec![pfx(a, s.network)],
+    };
+    let first = raw.chars().next().map_or(vec![], |c| if s.ignore_case { casings(c) } else { vec![c] });
+    let nexts: Vec<(&str, String)> = heads.iter().map(|h| (*h, next_chars(h, ver))).collect();
+    if nexts.iter().all(|(_, n)| !first.iter().any(|&c| n.contains(c))) {
+        let parts: Vec<String> = nexts.iter().map(|(h, n)| format!("'{}' 之后第一位只能是 {}", h, span(n))).collect();
+        return parts.join("，");
+    }
+    "超出该地址类型的数值范围（前缀过长或前导 1 过多）".into()
+}
+
+/// 按 Base58 顺序连续的字符写成 `a-b`
+fn span(chars: &str) -> String {
+    let idx: Vec<usize> = chars.chars().filter_map(|c| BASE58.find(c)).collect();
+    let mut out = vec![];
+    let mut i = 0;
+    while i < idx.len() {
+        let mut j = i;
+        while j + 1 < idx.len() && idx[j + 1] == idx[j] + 1 { j += 1; }
+        let (a, b) = (&BASE58[idx[i]..idx[i] + 1], &BASE58[idx[j]..idx[j] + 1]);
+        out.push(if i == j { a.to_string() } else { format!("{}-{}", a, b) });
+        i = j + 1;
+    }
+    out.join(" ")
+}
+
+/// 忽略大小写时单个目标展开的写法数上限，超出则放弃区间预筛
+const MAX_SPELLINGS: usize = 4096;
+
+/// Base58 目标的全部大小写写法
+fn spellings(raw: &str) -> Option<Vec<String>> {
+    let mut out = vec![String::new()];
+    for c in raw.chars() {
+        let cs = casings(c);
+        if out.len() * cs.len() > MAX_SPELLINGS { return None; }
+        out = out.iter().flat_map(|p| cs.iter().map(move |&x| format!("{}{}", p, x))).collect();
+    }
+    Some(out)
+}
+
+/// 超过这个数量的目标改用省内存的结构（Bloom 预筛 + 有序表滑动窗口）
+const BIG: usize = 100_000;
+
+/// 表中的一条: 字符串在 `data` 中的位置与目标下标
+#[derive(Clone, Copy)]
+struct Row { off: u32, len: u32, id: u32 }
+
+/// 有序目标表: 字符串连续存放，条目按字符串排序，查找时对每种长度二分；
+/// 条目很多时先查 Bloom 过滤器，省去大部分二分
+struct Table { lens: Vec<usize>, data: Vec<u8>, rows: Vec<Row>, bloom: Option<Bloom> }
+
+impl Table {
+    fn new<S: AsRef<str>>(items: impl IntoIterator<Item = (S, usize)>) -> Table {
+        let (mut data, mut rows) = (vec![], vec![]);
+        for (s, id) in items {
+            let s = s.as_ref();
+            rows.push(Row { off: data.len() as u32, len: s.len() as u32, id: id as u32 });
+            data.extend_from_slice(s.as_bytes());
+        }
+        let key = |r: &Row| &data[r.off as usize..(r.off + r.len) as usize];
+        rows.sort_unstable_by(|a, b| key(a).cmp(key(b)).then(a.id.cmp(&b.id)));
+
+        let mut lens: Vec<usize> = rows.iter().map(|r| r.len as usize).collect();
+        lens.sort_unstable();
+        lens.dedup();
+
+        let bloom = (rows.len() >= BIG).then(|| {
+            let mut b = Bloom::new(rows.len());
+            for r in &rows { b.insert(key(r)); }
+            b
+        });
+        Table { lens, data, rows, bloom }
+    }
+
+    fn key(&self, r: &Row) -> &[u8] { &self.data[r.off as usize..(r.off + r.len) as usize] }
+
+    /// 与 `key` 完全相同的条目对应的目标下标
+    fn get<'a>(&'a self, key: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+        let i = match &self.bloom {
+            Some(b) if !b.has(key) => self.rows.len(),
+            _ => self.rows.partition_point(|r| self.key(r) < key),
+        };
+        self.rows[i..].iter().take_while(move |r| self.key(r) == key).map(|r| r.id as usize)
+    }
+
+    /// `s` 的前缀（或后缀）中在表内的条目
+    fn ends<'a>(&'a self, s: &'a str, tail: bool) -> impl Iterator<Item = usize> + 'a {
+        let s = s.as_bytes();
+        self.lens.iter().take_while(move |&&l| l <= s.len())
+            .flat_map(move |&l| self.get(if tail { &s[s.len() - l..] } else { &s[..l] }))
+    }
+
+    /// 是否有以 `key` 开头、长度在 `lens` 内的条目（同前缀的条目在有序表中相邻）
+    fn starts(&self, key: &[u8], lens: RangeInclusive<usize>) -> bool {
+        let i = self.rows.partition_point(|r| self.key(r) < key);
+        self.rows[i..].iter().take_while(|r| self.key(r).starts_with(key))
+            .any(|r| lens.contains(&(r.len as usize)))
+    }
+
+    /// `s` 的所有子串中在表内的条目
+    fn within<'a>(&'a self, s: &'a str) -> impl Iterator<Item = usize> + 'a {
+        let s = s.as_bytes();
+        self.lens.iter().take_while(move |&&l| l <= s.len())
+            .flat_map(move |&l| s.windows(l).flat_map(move |w| self.get(w)))
+    }
+}
+
+/// Bloom 过滤器: 每个条目 16 位、4 个哈希，假阳性约 0.2%
+struct Bloom { bits: Vec<u64>, mask: u64 }
+
+impl Bloom {
+    fn new(n: usize) -> Bloom {
+        let size = (n * 16).next_power_of_two().max(64);
+        Bloom { bits: vec![0; size / 64], mask: size as u64 - 1 }
+    }
+
+    /// 双重哈希得到的 4 个位置
+    fn spots(&self, key: &[u8]) -> [u64; 4] {
+        // FNV-1a，再混合一次让高低位都均匀
+        let mut h = 0xcbf2_9ce4_8422_2325u64;
+        for &b in key { h = (h ^ b as u64).wrapping_mul(0x100_0000_01b3); }
+        h ^= h >> 33;
+        h = h.wrapping_mul(0xff51_afd7_ed55_8ccd);
+        h ^= h >> 33;
+        let (h1, h2) = (h, (h >> 32) | 1);
+        [0, 1, 2, 3].map(|i| h1.wrapping_add(i * h2) & self.mask)
+    }
+
+    fn insert(&mut self, key: &[u8]) {
+        for p in self.spots(key) { self.bits[(p / 64) as usize] |= 1 << (p % 64); }
+    }
+
+    fn has(&self, key: &[u8]) -> bool {
+        self.spots(key).iter().all(|p| self.bits[(p / 64) as usize] & (1 << (p % 64)) != 0)
+    }
+}
+
+/// 包含匹配: 目标不多时用 Aho–Corasick 自动机，很多时改用有序表滑动窗口（内存小得多）
+enum Inner { Ac(AhoCorasick, Vec<usize>, Table), Window(Table) }
+
+impl Inner {
+    fn new<'a>(items: &[(usize, &'a Target)], key: impl Fn(&'a Target) -> Cow<'a, str>) -> Inner {
+        let table = Table::new(items.iter().map(|(i, t)| (key(t), *i)));
+        if items.len() >= BIG { return Inner::Window(table); }
+        let ac = AhoCorasick::new(items.iter().map(|(_, t)| key(t).into_owned())).expect("目标数量超出自动机限制");
+        Inner::Ac(ac, items.iter().map(|(i, _)| *i).collect(), table)
+    }
+
+    fn table(&self) -> &Table {
+        match self { Inner::Ac(_, _, t) | Inner::Window(t) => t }
+    }
+
+    fn find(&self, s: &str, out: &mut Vec<usize>) {
+        match self {
+            Inner::Ac(ac, ids, _) => out.extend(ac.find_overlapping_iter(s).map(|m| ids[m.pattern().as_usize()])),
+            Inner::Window(t) => out.extend(t.within(s)),
+        }
+    }
+
+    fn any(&self, s: &str) -> bool {
+        match self {
+            Inner::Ac(ac, _, _) => ac.is_match(s),
+            Inner::Window(t) => t.within(s).next().is_some(),
+        }
+    }
+}
+
+/// Bech32 前缀对应见证程序开头的固定位；只比较前 64 位，按位数分组二分查找
+struct Bits { groups: Vec<(u32, Vec<u64>)> }
+
+impl Bits {
+    fn new<'a>(raws: impl Iterator<Item = &'a str>) -> Bits {
+        let mut keys: Vec<(u32, u64)> = raws.map(|raw| {
+            let chars = raw.len().min(13);
+            let acc = raw.chars().take(chars)
+                .fold(0u128, |acc, c| (acc << 5) | BECH32.find(c).unwrap_or(0) as u128);
+            let bits = (chars as u32 * 5).min(64);
+            (bits, (acc >> (chars as u32 * 5 - bits)) as u64)
+        }).collect();
+        keys.sort_unstable();
+        keys.dedup();
+
+        let mut groups: Vec<(u32, Vec<u64>)> = vec![];
+        for (bits, key) in keys {
+            match groups.last_mut() {
+                Some((b, ks)) if *b == bits => ks.push(key),
+                _ => groups.push((bits, vec![key])),
+            }
+        }
+        Bits { groups }
+    }
+
+    fn test(&self, prog: &[u8]) -> bool {
+        let p = u64::from_be_bytes(prog[..8].try_into().unwrap());
+        self.groups.iter().any(|(bits, ks)| ks.binary_search(&(p >> (64 - bits))).is_ok())
+    }
+}
+
+/// 见证程序按 5 位分组编码为 Bech32 字符（带零填充，不含校验和）
+pub fn encode5(prog: &[u8], out: &mut String) {
+    let (mut acc, mut n) = (0u32, 0);
+    for &b in prog {
+        acc = (acc << 8) | b as u32;
+        n += 8;
+        while n >= 5 {
+            n -= 5;
+            out.push(BECH32.as_bytes()[((acc >> n) & 31) as usize] as char);
+        }
+    }
+    if n > 0 { out.push(BECH32.as_bytes()[((acc << (5 - n)) & 31) as usize] as char); }
+}
+
+/// 预筛方式
+enum Kind {
+    /// 无法预筛，全部编码确认
+    Any,
+    /// Base58 前缀: 合并后的有序 hash160 区间
+    Ranges(Vec<Range>),
+    /// Bech32 前缀: 见证程序开头的位
+    Bits(Bits),
+    /// Bech32 后缀: 去掉校验和后，数据部分须以目标的前段结尾；不含校验和时须以整个目标结尾
+    Tail { pfx: &'static str, checksum: bool },
+    /// Bech32 包含: 目标完整出现在数据中，或其前段出现在数据末尾（其余落在校验和内）
+    Inner { pfx: &'static str, checksum: bool },
+}
+
+/// 两端目标: 去掉固定前缀后的开头与地址结尾，`?` 处任意
+struct Mask { id: usize, head: Vec<u8>, tail: Vec<u8> }
+
+impl Mask {
+    fn test(&self, body: &[u8]) -> bool {
+        let eq = |p: &[u8], s: &[u8]| p.iter().zip(s).all(|(&a, &b)| a == ANY as u8 || a == b);
+        body.len() >= self.head.len() + self.tail.len()
+            && eq(&self.head, body)
+            && eq(&self.tail, &body[body.len() - self.tail.len()..])
+    }
+}
+
+/// 前缀部分开头到第一个占位符之前的固定字符，用于预筛和查表
+pub fn lead(head: &str) -> &str {
+    &head[..head.find(ANY).unwrap_or(head.len())]
+}
+
+/// 精确匹配: 前缀/后缀用有序表，包含用自动机或滑动窗口，模式逐个跑 DFA，
+/// 两端目标先按开头固定字符查表再逐个比对
+enum Exact { Prefix(Table), Suffix(Table), Contains(Inner), Pattern(Vec<(Pattern, usize)>), Both(Table, Vec<Mask>) }
+
+/// 同一匹配模式的一组目标
+struct Part { mode: Match, kind: Kind, exact: Exact }
+
+impl Part {
+    fn new<'a>(s: &Settings, mode: Match, items: &[(usize, &'a Target)]) -> Part {
+        let a = s.addr_type;
+        // 有目标可能完全落在校验和内时，后缀/包含无法预筛
+        let short = s.checksum && items.iter().any(|(_, t)| t.raw.len() <= CHECKSUM);
+        // 前缀预筛的依据: 前缀目标本身，或两端目标开头的固定字符
+        let leads: Vec<&str> = items.iter().map(|(_, t)| t.ends().map_or(t.raw.as_str(), |(h, _)| lead(h))).collect();
+        let kind = match (is_bech32(a), mode) {
+            (_, Match::Both) if leads.iter().any(|l| l.is_empty()) => Kind::Any,
+            (false, Match::Prefix | Match::Both) => {
+                let ver = version(a, is_main(s.network));
+                let fulls: Option<Vec<_>> = leads.iter().map(|l| prefix_fulls(l, s)).collect();
+                match fulls {
+                    Some(fulls) => Kind::Ranges(merge(fulls.iter().flatten()
+                        .flat_map(|full| prefix_ranges(full, ver))
+                        .collect())),
+                    None => Kind::Any,
+                }
+            }
+            (true, Match::Prefix | Match::Both) => Kind::Bits(Bits::new(leads.iter().copied())),
+            (_, Match::Pattern) | (false, _) => Kind::Any,
+            (true, _) if short => Kind::Any,
+            (true, Match::Suffix) => Kind::Tail { pfx: pfx(a, s.network), checksum: s.checksum },
+            (true, Match::Contains) => Kind::Inner { pfx: pfx(a, s.network), checksum: s.checksum },
+        };
+
+        // 忽略大小写时表中存小写，查找时地址也转小写
+        let fold = s.ignore_case && !is_bech32(a);
+        let key = |t: &'a Target| if fold { Cow::Owned(t.raw.to_lowercase()) } else { Cow::Borrowed(t.raw.as_str()) };
+        let raws = || items.iter().map(|(i, t)| (key(t), *i));
+        let exact = match mode {
+            Match::Prefix => Exact::Prefix(Table::new(raws())),
+            Match::Suffix => Exact::Suffix(Table::new(raws())),
+            Match::Contains => Exact::Contains(Inner::new(items, key)),
+            // 目标已在解析时校验过，这里编译不会失败
+            Match::Pattern => Exact::Pattern(items.iter()
+                .filter_map(|(i, t)| Some((Pattern::compile(&t.raw, s).ok()?, *i)))
+                .collect()),
+            Match::Both => {
+                let masks: Vec<Mask> = items.iter().filter_map(|(i, t)| {
+                    let (h, tl) = t.ends()?;
+                    let k = |x: &str| if fold { x.to_lowercase().into_bytes() } else { x.as_bytes().to_vec() };
+                    Some(Mask { id: *i, head: k(h), tail: k(tl) })
+                }).collect();
+                // 表中条目的下标指向 masks
+                let table = Table::new(leads.iter().enumerate().map(|(j, l)| (if fold { l.to_lowercase() } else { l.to_string() }, j)));
+                Exact::Both(table, masks)
+            }
+        };
+        Part { mode, kind, exact }
+    }
+
+    fn maybe(&self, prog: &[u8], buf: &mut String) -> bool {
+        match &self.kind {
+            Kind::Any => true,
+            Kind::Ranges(rs) => {
+                let i = rs.partition_point(|r| &r.1[..] < prog);
+                i < rs.len() && &rs[i].0[..] <= prog
+            }
+            Kind::Bits(b) => b.test(prog),
+            // 预筛复用精确匹配的有序表: 数据部分末尾的 L 个字符须是某个目标的开头
+            // 只看数据部分（不含 HRP 与见证版本）；不含校验和时部分编码就是完整的匹配区域
+            Kind::Tail { pfx, checksum } => {
+                let Exact::Suffix(t) = &self.exact else { return true };
+                if buf.is_empty() { head(pfx, prog, buf); }
+                let b = &buf.as_bytes()[pfx.len()..];
+                if !checksum { return t.ends(&buf[pfx.len()..], true).next().is_some(); }
+                t.lens.iter().map(|l| l - CHECKSUM).filter(|&l| l <= b.len())
+                    .any(|l| t.starts(&b[b.len() - l..], l + CHECKSUM..=l + CHECKSUM))
+            }
+            Kind::Inner { pfx, checksum } => {
+                let Exact::Contains(inner) = &self.exact else { return true };
+                if buf.is_empty() { head(pfx, prog, buf); }
+                let data = &buf[pfx.len()..];
+                if !checksum { return inner.any(data); }
+                let (t, b) = (inner.table(), data.as_bytes());
+                let min = t.lens.first().map_or(1, |l| l.saturating_sub(CHECKSUM).max(1));
+                let max = t.lens.last().copied().unwrap_or(0).min(b.len() + 1);
+                inner.any(data) || (min..max).any(|l| t.starts(&b[b.len() - l..], l + 1..=l + CHECKSUM))
+            }
+        }
+    }
+
+    /// `key` 为用于字面目标比较的地址（忽略大小写时为小写），`region` 为其中参与后缀/包含匹配的部分；
+    /// 模式直接匹配完整的 `addr`
+    fn find(&self, addr: &str, key: &str, skip: usize, region: &str, out: &mut Vec<usize>) {
+        match &self.exact {
+            Exact::Prefix(t) => out.extend(t.ends(&key[skip..], false)),
+            Exact::Suffix(t) => out.extend(t.ends(region, true)),
+            Exact::Contains(inner) => inner.find(region, out),
+            Exact::Pattern(ps) => out.extend(ps.iter().filter(|(p, _)| p.is_match(addr)).map(|(_, i)| *i)),
+            Exact::Both(t, masks) => {
+                out.extend(t.ends(region, false).map(|j| &masks[j]).filter(|m| m.test(region.as_bytes())).map(|m| m.id));
+            }
+        }
+    }
+}
+
+/// 全部目标的匹配器，按匹配模式分组
+pub struct Matcher {
+    parts: Vec<Part>,
+    fold: bool,
+    /// 后缀/包含匹配时去掉的末尾校验和字符数
+    cut: usize,
+}
+
+impl Matcher {
+    pub fn new(s: &Settings, targets: &[Target]) -> Matcher {
+        let parts = [Match::Prefix, Match::Suffix, Match::Contains, Match::Pattern, Match::Both].into_iter().filter_map(|m| {
+            let items: Vec<_> = targets.iter().enumerate().filter(|(_, t)| t.mode == m).collect();
+            (!items.is_empty()).then(|| Part::new(s, m, &items))
+        }).collect();
+        Matcher {
+            parts,
+            fold: s.ignore_case && !is_bech32(s.addr_type),
+            cut: if s.checksum { 0 } else { checksum_len(s.addr_type) },
+        }
+    }
+
+    /// 该模式的目标是否无法预筛（每个候选都要编码）
+    fn loose(&self, mode: Match) -> bool {
+        self.parts.iter().any(|p| p.mode == mode && matches!(p.kind, Kind::Any))
+    }
+
+    /// false 表示一定不命中；true 需要编码地址确认。`buf` 为空时按需写入部分编码，
+    /// 同一候选的后续调用（包括近似命中的预筛）直接复用，换候选前须清空
+    pub fn maybe(&self, prog: &[u8], buf: &mut String) -> bool {
+        self.parts.iter().any(|p| p.maybe(prog, buf))
+    }
+
+    /// 地址字符串命中的全部目标下标（升序）；开头 `skip` 个固定字符（HRP 与见证版本，或 Base58 版本字符）不参与字面匹配
+    pub fn find(&self, addr: &str, skip: usize) -> Vec<usize> {
+        let mut out = vec![];
+        let low = self.fold.then(|| addr.to_lowercase());
+        let key = low.as_deref().unwrap_or(addr);
+        let region = &key[skip..key.len().saturating_sub(self.cut).max(skip)];
+        for p in &self.parts { p.find(addr, key, skip, region, &mut out); }
+        if out.len() > 1 {
+            out.sort_unstable();
+            out.dedup();
+        }
+        out
+    }
+}
+
+/// 近似命中的预筛目标: 平均每次尝试通过的概率不超过这个值，编码开销可以忽略
+const NEAR_RATE: f64 = 1e-4;
+
+/// 近似命中: 目标截短到同一位数另建一个匹配器预筛，通过的地址再算实际匹配了几位。
+/// 模式目标不参与；两端目标只看开头的固定字符做预筛
+pub struct Near {
+    matcher: Matcher,
+    /// 截短目标对应的原目标下标
+    ids: Vec<usize>,
+    /// 计算匹配位数时去掉的末尾校验和字符数（按原设置，预筛用的匹配器可能不同）
+    cut: usize,
+}
+
+impl Near {
+    /// 选最小的截短位数使预筛通过率足够低；没有比它更长的目标时返回 None。
+    /// `exact` 为完整目标的匹配器
+    pub fn new(s: &Settings, targets: &[Target], exact: &Matcher) -> Option<Near> {
+        let (a, nocase) = (s.addr_type, s.ignore_case);
+        let body = |t: &Target| -> String {
+            match t.ends() {
+                Some((h, _)) => lead(h).to_string(),
+                None => t.raw.clone(),
+            }
+        };
+        // Bech32 包括校验和时，截短的目标多半落进校验和而无法预筛: 包含目标只在数据部分里预筛，
+        // 后缀目标不跟踪
+        let sum = s.checksum && is_bech32(a);
+        let items: Vec<(usize, &Target, String)> = targets.iter().enumerate()
+            .filter(|(_, t)| t.mode != Match::Pattern && !(sum && t.mode == Match::Suffix))
+            .map(|(i, t)| (i, t, body(t)))
+            .collect();
+        let longest = items.iter().map(|(_, _, b)| b.chars().count()).max()?;
+        // 包含匹配可以出现在数据部分的任意位置
+        let spots = match a { Addr::Taproot => 52.0, Addr::SegWit => 32.0, _ => 27.0 };
+        let rate = |l: usize| -> f64 {
+            items.iter().filter(|(_, _, b)| b.len() > l).map(|(_, t, b)| {
+                let p = 1.0 / exp(&b[..l], a, nocase) as f64;
+                if t.mode == Match::Contains { p * spots } else { p }
+            }).sum()
+        };
+        let min = (1..longest).find(|&l| rate(l) <= NEAR_RATE)?;
+        let (ids, cut): (Vec<usize>, Vec<Target>) = items.iter().filter(|(_, _, b)| b.len() > min).map(|(i, t, b)| {
+            let (raw, mode) = match t.mode {
+                Match::Suffix => (b[b.len() - min..].to_string(), Match::Suffix),
+                Match::Contains => (b[..min].to_string(), Match::Contains),
+                _ => (b[..min].to_string(), Match::Prefix),
+            };
+            (*i, Target { raw, mode, priority: 0 })
+        }).unzip();
+        let mut matcher = Matcher::new(&Settings { checksum: s.checksum && !sum, ..s.clone() }, &cut);
+        // 精确匹配本来能预筛时，为近似命中逐个编码不划算
+        matcher.parts.retain(|p| !matches!(p.kind, Kind::Any) || exact.loose(p.mode));
+        let cut = if s.checksum { 0 } else { checksum_len(a) };
+        (!matcher.parts.is_empty()).then_some(Near { matcher, ids, cut })
+    }
+
+    pub fn maybe(&self, prog: &[u8], buf: &mut String) -> bool {
+        self.matcher.maybe(prog, buf)
+    }
+
+    /// 地址接近命中的各目标及其匹配位数
+    pub fn find(&self, addr: &str, skip: usize, targets: &[Target]) -> Vec<(usize, usize)> {
+        let hits = self.matcher.find(addr, skip);
+        if hits.is_empty() { return vec![]; }
+        let low = self.matcher.fold.then(|| addr.to_lowercase());
+        let key = low.as_deref().unwrap_or(addr);
+        let region = &key[skip..key.len().saturating_sub(self.cut).max(skip)];
+        hits.into_iter().map(|j| {
+            let t = &targets[self.ids[j]];
+            let raw = if self.matcher.fold { t.raw.to_lowercase() } else { t.raw.clone() };
+            (self.ids[j], partial(t.mode, raw.as_bytes(), &key.as_bytes()[skip..], region.as_bytes()))
+        }).collect()
+    }
+}
+
+/// 目标 `raw` 在地址中匹配上的字符数: 前缀 / 后缀为相同的开头 / 结尾，包含为出现过的最长开头，
+/// 两端为前后两部分各自从外向内连续相同的固定字符之和
+fn partial(mode: Match, raw: &[u8], body: &[u8], region: &[u8]) -> usize {
+    let same = |a: &[u8], b: &[u8]| a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    match mode {
+        Match::Prefix => same(raw, body),
+        Match::Suffix => raw.iter().rev().zip(region.iter().rev()).take_while(|(x, y)| x == y).count(),
+        Match::Contains => (1..=raw.len()).rev().find(|&k| region.windows(k).any(|w| w == &raw[..k])).unwrap_or(0),
+        Match::Both => {
+            let s = std::str::from_utf8(raw).unwrap_or("");
+            let (h, t) = s.split_once(crate::config::ELLIPSIS).unwrap_or((s, ""));
+            let fixed = |p: &mut dyn Iterator<Item = (&u8, &u8)>| p.take_while(|(x, y)| **x == ANY as u8 || x == y)
+                .filter(|(x, _)| **x != ANY as u8).count();
+            fixed(&mut h.as_bytes().iter().zip(region)) + fixed(&mut t.as_bytes().iter().rev().zip(region.iter().rev()))
+        }
+        Match::Pattern => 0,
+    }
+}
+
+/// 区间按下界排序并合并重叠部分，便于二分查找
+fn merge(mut rs: Vec<Range>) -> Vec<Range> {
+    rs.sort_unstable();
+    let mut out: Vec<Range> = Vec::with_capacity(rs.len());
+    for r in rs {
+        match out.last_mut() {
+            Some(last) if r.0 <= last.1 => last.1 = last.1.max(r.1),
+            _ => out.push(r),
+        }
+    }
+    out
+}
+
+/// 固定前缀 + 数据部分（不含校验和）
+fn head(pfx: &str, prog: &[u8], buf: &mut String) {
+    buf.clear();
+    buf.push_str(pfx);
+    encode5(prog, buf);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::addr::pfx_len;
+    use bitcoin::address::{Payload, WitnessProgram, WitnessVersion};
+    use bitcoin::{Address, Network};
+    use rand::{Rng, RngCore};
+    use std::collections::{HashMap, HashSet};
+
+    /// 随机原始数据（见证程序或 hash160）及其完整地址
+    fn random(a: Addr, rng: &mut impl RngCore) -> (Vec<u8>, String) {
+        let mut prog = vec![0u8; if a == Addr::Taproot { 32 } else { 20 }];
+        rng.fill_bytes(&mut prog);
+        let addr = match a {
+            Addr::Taproot | Addr::SegWit => {
+                let v = if a == Addr::Taproot { WitnessVersion::V1 } else { WitnessVersion::V0 };
+                Address::new(Network::Bitcoin, Payload::WitnessProgram(WitnessProgram::new(v, prog.clone()).unwrap())).to_string()
+            }
+            _ => bitcoin::base58::encode_check(&[&[version(a, true)][..], &prog].concat()),
+        };
+        (prog, addr)
+    }
+
+    fn target(raw: &str, mode: Match) -> Target { Target { raw: raw.into(), mode, priority: 0 } }
+
+    fn word(rng: &mut impl Rng, alphabet: &[u8], n: usize) -> String {
+        (0..n).map(|_| alphabet[rng.gen_range(0..alphabet.len())] as char).collect()
+    }
+
+    #[test]
+    fn encode5_and_bits_match_full_encode() {
+        let mut rng = rand::thread_rng();
+        let chars = BECH32.as_bytes();
+        for a in [Addr::SegWit, Addr::Taproot] {
+            let skip = pfx_len(a, Network::Bitcoin);
+            for _ in 0..300 {
+                let (prog, addr) = random(a, &mut rng);
+                let body = &addr[skip..];
+                let mut enc = String::new();
+                encode5(&prog, &mut enc);
+                assert_eq!(enc, body[..body.len() - CHECKSUM], "{}", addr);
+
+                for l in 1..=20 {
+                    // 地址本身的开头一定通过
+                    assert!(Bits::new([&body[..l]].into_iter()).test(&prog), "{} / {}", addr, &body[..l]);
+                    // 12 位以内只比较固定位，与字符串比较完全一致
+                    let other = word(&mut rng, chars, l);
+                    let bits = Bits::new([other.as_str()].into_iter()).test(&prog);
+                    if l <= 12 { assert_eq!(bits, body.starts_with(&other), "{} / {}", addr, other); }
+                    else if body.starts_with(&other) { assert!(bits); }
+                }
+            }
+        }
+    }
+
+    /// 从地址中取出必定命中的目标，另加一些随机目标；命中时预筛必须通过
+    #[test]
+    fn prefilter_has_no_false_negatives() {
+        let mut rng = rand::thread_rng();
+        for a in [Addr::SegWit, Addr::Taproot, Addr::Legacy] {
+            for checksum in [true, false] {
+                for nocase in [false, true] {
+                    if nocase && is_bech32(a) { continue; }
+                    let s = Settings { addr_type: a, checksum, ignore_case: nocase, ..Settings::default() };
+                    let skip = pfx_len(a, Network::Bitcoin);
+                    for _ in 0..200 {
+                        let (prog, addr) = random(a, &mut rng);
+                        let key = if nocase { addr.to_lowercase() } else { addr.clone() };
+                        let body = &key[skip..];
+                        let region = &body[..body.len() - if checksum { 0 } else { checksum_len(a) }];
+                        let l = rng.gen_range(2..=8);
+                        let i = rng.gen_range(0..=region.len() - l);
+                        let mut ts = vec![
+                            target(&body[..l], Match::Prefix),
+                            target(&region[region.len() - l..], Match::Suffix),
+                            target(&region[i..i + l], Match::Contains),
+                            target(&format!("{}?...{}", &body[..2], &region[region.len() - 2..]), Match::Both),
+                        ];
+                        let (_, decoy) = random(a, &mut rng);
+                        ts.push(target(&decoy[skip..skip + 4], Match::Prefix));
+                        ts.push(target(&decoy[decoy.len() - 7..decoy.len() - 2], Match::Contains));
+                        // 逐个目标单独建匹配器，各种预筛方式都覆盖到
+                        for t in &ts {
+                            let m = Matcher::new(&s, std::slice::from_ref(t));
+                            let hit = !m.find(&addr, skip).is_empty();
+                            let mut buf = String::new();
+                            assert!(!hit || m.maybe(&prog, &mut buf), "{:?} {} 预筛漏掉 {:?}", a, addr, t);
+                        }
+                        let m = Matcher::new(&s, &ts);
+                        let found = m.find(&addr, skip);
+                        assert!((0..4).all(|i| found.contains(&i)), "{} {:?} {:?}", addr, ts, found);
+                        assert!(m.maybe(&prog, &mut String::new()));
+                    }
+                }
+            }
+        }
+    }
+
+    /// 两端目标中 `?` 的位置换成任何字符都命中，固定字符改掉一个就不命中
+    #[test]
+    fn mask_placeholders_match_anything() {
+        let mut rng = rand::thread_rng();
+        for a in [Addr::SegWit, Addr::Taproot, Addr::Legacy] {
+            let s = Settings { addr_type: a, ..Settings::default() };
+            let skip = pfx_len(a, Network::Bitcoin);
+            let alphabet = if is_bech32(a) { BECH32.as_bytes() } else { BASE58.as_bytes() };
+            for _ in 0..300 {
+                let (_, addr) = random(a, &mut rng);
+                let body: Vec<char> = addr[skip..].chars().collect();
+                let (h, t) = (rng.gen_range(2..=6), rng.gen_range(0..=4));
+                let mut head: Vec<char> = body[..h].to_vec();
+                let mut tail: Vec<char> = body[body.len() - t..].to_vec();
+                // 开头第一位保留，其余每位一半换成占位符
+                for c in head.iter_mut().skip(1).chain(tail.iter_mut()) {
+                    if rng.gen() { *c = ANY; }
+                }
+                let raw = |head: &[char], tail: &[char]| format!("{}...{}", head.iter().collect::<String>(), tail.iter().collect::<String>());
+                let m = Matcher::new(&s, &[target(&raw(&head, &tail), Match::Both)]);
+                assert_eq!(m.find(&addr, skip), vec![0], "{} {}", addr, raw(&head, &tail));
+                // 把一个固定字符换成别的
+                let fixed: Vec<usize> = (0..h + t).filter(|&i| if i < h { head[i] != ANY } else { tail[i - h] != ANY }).collect();
+                let i = fixed[rng.gen_range(0..fixed.len())];
+                let c = if i < h { &mut head[i] } else { &mut tail[i - h] };
+                let old = *c;
+                while *c == old { *c = alphabet[rng.gen_range(0..alphabet.len())] as char; }
+                let m = Matcher::new(&s, &[target(&raw(&head, &tail), Match::Both)]);
+                assert!(m.find(&addr, skip).is_empty(), "{} {}", addr, raw(&head, &tail));
+            }
+        }
+    }
+
+    /// 参照实现: 直接在完整地址上比较字符
+    fn brute(mode: Match, raw: &str, body: &str, region: &str) -> usize {
+        let same = |a: &mut dyn Iterator<Item = char>, b: &mut dyn Iterator<Item = char>| a.zip(b).take_while(|(x, y)| x == y).count();
+        match mode {
+            Match::Prefix => same(&mut raw.chars(), &mut body.chars()),
+            Match::Suffix => same(&mut raw.chars().rev(), &mut region.chars().rev()),
+            Match::Contains => (0..=raw.len()).filter(|&k| region.contains(&raw[..k])).max().unwrap_or(0),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn near_reports_planted_partials() {
+        let mut rng = rand::thread_rng();
+        let chars = BECH32.as_bytes();
+        for (a, checksum) in [(Addr::SegWit, true), (Addr::SegWit, false), (Addr::Taproot, false), (Addr::Legacy, true)] {
+            let s = Settings { addr_type: a, checksum, ..Settings::default() };
+            let skip = pfx_len(a, Network::Bitcoin);
+            let alphabet = if is_bech32(a) { chars } else { BASE58.as_bytes() };
+            let mut tested = 0;
+            for _ in 0..300 {
+                let (prog, addr) = random(a, &mut rng);
+                let body = &addr[skip..];
+                let region = &body[..body.len() - if checksum { 0 } else { checksum_len(a) }];
+                // 前 k 位取自地址，之后随机，长 12 位
+                let k = rng.gen_range(3..=10);
+                let i = rng.gen_range(0..region.len() - k);
+                let mut ts = vec![
+                    target(&format!("{}{}", &body[..k], word(&mut rng, alphabet, 12 - k)), Match::Prefix),
+                    target(&format!("{}{}", &region[i..i + k], word(&mut rng, alphabet, 12 - k)), Match::Contains),
+                ];
+                // Bech32 包括校验和时不跟踪后缀
+                if !(checksum && is_bech32(a)) {
+                    ts.push(target(&format!("{}{}", word(&mut rng, alphabet, 12 - k), &region[region.len() - k..]), Match::Suffix));
+                }
+                let exact = Matcher::new(&s, &ts);
+                let Some(near) = Near::new(&s, &ts, &exact) else { panic!("{:?} 没有近似命中", a) };
+                let got = near.find(&addr, skip, &ts);
+                // Bech32 包括校验和时只在数据部分预筛，落在校验和里的部分匹配可能被漏掉
+                let data = if checksum && is_bech32(a) { &region[..region.len() - CHECKSUM] } else { region };
+                for (j, t) in ts.iter().enumerate() {
+                    let want = brute(t.mode, &t.raw, body, region);
+                    let n = got.iter().find(|g| g.0 == j).map(|g| g.1);
+                    if let Some(n) = n { assert_eq!(n, want, "{} {:?}", addr, t); }
+                    // 达到截短位数的一定被预筛放过并报告
+                    if brute(t.mode, &t.raw, body, data) >= min_len(&near) {
+                        assert!(near.maybe(&prog, &mut String::new()), "{} {:?}", addr, t);
+                        assert_eq!(n, Some(want), "{} {:?}", addr, t);
+                        tested += 1;
+                    }
+                }
+            }
+            assert!(tested > 100, "{:?} 只检查了 {} 个", a, tested);
+        }
+    }
+
+    /// 近似命中预筛用的截短位数
+    fn min_len(n: &Near) -> usize {
+        n.matcher.parts.iter().flat_map(|p| match &p.exact {
+            Exact::Prefix(t) | Exact::Suffix(t) => t.lens.clone(),
+            Exact::Contains(i) => i.table().lens.clone(),
+            _ => vec![],
+        }).min().unwrap()
+    }
+
+    #[test]
+    fn bloom_has_no_false_negatives() {
+        let mut rng = rand::thread_rng();
+        let keys: Vec<[u8; 8]> = (0..200_000).map(|_| rng.gen()).collect();
+        let mut b = Bloom::new(keys.len());
+        for k in &keys { b.insert(k); }
+        assert!(keys.iter().all(|k| b.has(k)));
+        // 假阳性约 0.2%
+        let fp = (0..100_000).filter(|_| b.has(&rng.gen::<[u8; 9]>())).count();
+        assert!(fp < 1000, "假阳性 {}", fp);
+    }
+
+    #[test]
+    fn big_table_matches_naive() {
+        let mut rng = rand::thread_rng();
+        let chars = BECH32.as_bytes();
+        // 超过 BIG 时走 Bloom + 有序表；短词多，随机串里能查到不少
+        let words: Vec<String> = (0..BIG + 1000).map(|i| word(&mut rng, chars, 3 + i % 4)).collect();
+        let t = Table::new(words.iter().enumerate().map(|(i, w)| (w.as_str(), i)));
+        assert!(t.bloom.is_some());
+        let mut by: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (i, w) in words.iter().enumerate() { by.entry(w).or_default().push(i); }
+        let mut hits = 0;
+        for _ in 0..300 {
+            let s = word(&mut rng, chars, 20);
+            let within: HashSet<usize> = t.within(&s).collect();
+            // 参照: 逐个子串查哈希表
+            let naive: HashSet<usize> = (0..s.len()).flat_map(|i| (i + 1..=s.len()).map(move |j| (i, j)))
+                .flat_map(|(i, j)| by.get(&s[i..j]).into_iter().flatten().copied()).collect();
+            assert_eq!(within, naive, "{}", s);
+            let ends: HashSet<usize> = t.ends(&s, false).collect();
+            assert_eq!(ends, naive.iter().copied().filter(|&i| s.starts_with(&words[i])).collect());
+            let tails: HashSet<usize> = t.ends(&s, true).collect();
+            assert_eq!(tails, naive.iter().copied().filter(|&i| s.ends_with(&words[i])).collect());
+            hits += naive.len();
+        }
+        assert!(hits > 0);
+        assert!(words.iter().enumerate().step_by(97).all(|(i, w)| t.get(w.as_bytes()).any(|j| j == i)));
+    }
+}
@@ -0,0 +1,90 @@
+//! 命中概率与 ETA
+//!
+//! 每次尝试独立，命中任一目标的概率为各目标 1/E 之和 p。到首次命中的尝试次数服从几何分布，
+//! n 次内至少命中一次的概率为 1 - (1-p)^n，线性的 "已尝试 / 期望" 会误导。
+
+/// 多个目标合并后的命中率
+#[derive(Clone, Debug)]
+pub struct Odds { rates: Vec<f64>, rate: f64 }
+
+impl Odds {
+    /// 由各目标的期望尝试次数构造
+    pub fn new(exps: impl IntoIterator<Item = u64>) -> Odds {
+        let rates: Vec<f64> = exps.into_iter().map(|e| 1.0 / e.max(1) as f64).collect();
+        let rate = rates.iter().sum::<f64>().min(1.0);
+        Odds { rates, rate }
+    }
+
+    /// 每次尝试命中任一目标的概率
+    pub fn rate(&self) -> f64 { self.rate }
+
+    /// 首次命中的期望尝试次数
+    pub fn expected(&self) -> f64 { 1.0 / self.rate }
+
+    /// `n` 次尝试内至少命中一次的概率
+    pub fn cumulative(&self, n: u64) -> f64 { cdf(self.rate, n) }
+
+    /// 至少命中一次的概率达到 `q` 所需的尝试次数
+    pub fn attempts_for(&self, q: f64) -> f64 {
+        if self.rate >= 1.0 { return 1.0; }
+        (-q).ln_1p() / (-self.rate).ln_1p()
+    }
+
+    /// 第 `i` 个目标: 首次命中落在它上面的概率，以及它单独在 `n` 次内被命中的概率
+    pub fn target(&self, i: usize, n: u64) -> (f64, f64) {
+        (self.rates[i] / self.rate, cdf(self.rates[i], n))
+    }
+}
+
+fn cdf(p: f64, n: u64) -> f64 {
+    if p >= 1.0 { return if n > 0 { 1.0 } else { 0.0 }; }
+    -(n as f64 * (-p).ln_1p()).exp_m1()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64, tol: f64) -> bool { (a - b).abs() <= tol * b.abs().max(1.0) }
+
+    #[test]
+    fn cumulative_at_known_points() {
+        let n = 1_000_000;
+        let o = Odds::new([n]);
+        assert_eq!(o.cumulative(0), 0.0);
+        // 尝试次数等于期望时约为 1 - 1/e
+        assert!(close(o.cumulative(n), 1.0 - (-1f64).exp(), 1e-6), "{}", o.cumulative(n));
+        assert!(close(o.cumulative(2 * n), 1.0 - (-2f64).exp(), 1e-6));
+        assert!(o.cumulative(100 * n) > 0.999_999);
+        // 每次必中
+        let o = Odds::new([1]);
+        assert_eq!((o.cumulative(0), o.cumulative(1), o.attempts_for(0.99)), (0.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn percentiles_invert_cumulative() {
+        let n = 1_000_000f64;
+        let o = Odds::new([n as u64]);
+        // 50% / 90% / 99% 分别约为 N·ln2、N·ln10、N·ln100
+        for (q, want) in [(0.5, n * 2f64.ln()), (0.9, n * 10f64.ln()), (0.99, n * 100f64.ln())] {
+            let k = o.attempts_for(q);
+            assert!(close(k, want, 1e-5), "{} {} {}", q, k, want);
+            assert!(close(o.cumulative(k.round() as u64), q, 1e-5));
+        }
+    }
+
+    #[test]
+    fn targets_combine() {
+        let o = Odds::new([100, 400]);
+        // 合并命中率 1/100 + 1/400 = 1/80
+        assert!(close(o.rate(), 0.0125, 1e-12));
+        assert!(close(o.expected(), 80.0, 1e-9));
+        // 1 - (1 - 0.0125)^100
+        assert!(close(o.cumulative(100), 0.715_743_483_3, 1e-9), "{}", o.cumulative(100));
+        // 首次命中落在各目标上的比例与单独命中的概率
+        let (share, p) = o.target(0, 100);
+        assert!(close(share, 0.8, 1e-12) && close(p, 1.0 - 0.99f64.powi(100), 1e-9));
+        let (share, p) = o.target(1, 400);
+        assert!(close(share, 0.2, 1e-12) && close(p, 0.632_580_887_7, 1e-9));
+    }
+}
@@ -0,0 +1,522 @@
+//! 模式匹配: 正则表达式子集编译为字符集上的 DFA
+//!
+//! 支持 `.`、字符类 `[a-z]` / `[^...]`、`\d`、分组与 `|`、量词 `* + ? {n} {n,} {n,m}`，
+//! 模式首尾的 `^` / `$`，以及单字符分组的反向引用（如 `(.)\1{5}` 表示 6 个相同字符）。
+//! 匹配对象是完整地址字符串（含 `bc1q` 等固定前缀）。Base58 忽略大小写时字面字符同时匹配两种写法。
+
+use crate::addr::{charset, is_bech32, is_main, pfx, Addr};
+use crate::base58::{next_chars, prefix_prob};
+use crate::config::Settings;
+use crate::matcher::version;
+use std::collections::HashMap;
+
+/// NFA / DFA 状态数上限，超出视为模式过于复杂
+const MAX_STATES: usize = 20_000;
+/// 子集构造中全部 DFA 状态所含 NFA 状态总数的上限，限制编译时间
+const MAX_WORK: usize = 1_000_000;
+/// 反向引用展开的副本数上限
+const MAX_COPIES: usize = 4096;
+/// 量词次数上限（地址不会更长）
+const MAX_REP: u32 = 90;
+const NONE: u8 = u8::MAX;
+
+/// 语法树；字符集合用符号位图表示
+#[derive(Clone, Debug)]
+enum Node {
+    Set(u64),
+    Cat(Vec<Node>),
+    Alt(Vec<Node>),
+    Rep(Box<Node>, u32, Option<u32>),
+    Group(usize, Box<Node>),
+    Ref(usize),
+}
+
+/// 地址字符串模型: 每个位置可能出现的字符（等概率）
+///
+/// Base58 版本字符之后的一位受版本字节限制（如 P2SH 为 `3` 后只能是 `1`-`R`），
+/// 用区间换算筛掉不可能的字符。测试网 Legacy 的 `m` / `n` 后续范围不同，
+/// 拆成两个模型，按各自出现的概率加权。
+fn model(s: &Settings) -> Vec<(f64, Vec<String>)> {
+    let (a, n) = (s.addr_type, s.network);
+    let heads: Vec<String> = match (a, is_main(n)) {
+        (Addr::Legacy, false) => vec!["m".into(), "n".into()],
+        _ => vec![pfx(a, n).to_string()],
+    };
+    let total = match (a, is_main(n)) {
+        (Addr::Taproot, _) => pfx(a, n).len() + 52 + 6,
+        (Addr::SegWit, _) => pfx(a, n).len() + 32 + 6,
+        (Addr::P2SH, false) => 35,
+        _ => 34,
+    };
+    let mut out: Vec<(f64, Vec<String>)> = heads.iter().map(|h| {
+        let mut m: Vec<String> = h.chars().map(String::from).collect();
+        let w = if is_bech32(a) { 1.0 } else {
+            let ver = version(a, is_main(n));
+            m.push(next_chars(h, ver));
+            prefix_prob(h, ver)
+        };
+        m.resize(total, charset(a).to_string());
+        (w, m)
+    }).collect();
+    let sum: f64 = out.iter().map(|(w, _)| w).sum();
+    for (w, _) in &mut out { *w /= sum; }
+    out
+}
+
+struct Parser<'a> {
+    s: &'a [u8],
+    pos: usize,
+    /// 字节 → 符号
+    map: &'a [u8; 256],
+    all: u64,
+    groups: Vec<Option<u64>>,
+    /// Base58 忽略大小写: 字面字符同时匹配另一种写法
+    fold: bool,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<u8> { self.s.get(self.pos).copied() }
+
+    fn eat(&mut self, c: u8) -> bool {
+        if self.peek() == Some(c) { self.pos += 1; true } else { false }
+    }
+
+    /// 字符 `c`（忽略大小写时连同另一种写法）在字符集中的符号位
+    fn bits(&self, c: u8) -> u64 {
+        let cs = if self.fold { [c.to_ascii_lowercase(), c.to_ascii_uppercase()] } else { [c, c] };
+        cs.iter().filter(|&&x| self.map[x as usize] != NONE).fold(0, |m, &x| m | 1 << self.map[x as usize])
+    }
+
+    fn sym(&self, c: u8) -> Result<u64, String> {
+        match self.bits(c) {
+            0 => Err(format!("字符 '{}' 不在字符集中", c as char)),
+            m => Ok(m),
+        }
+    }
+
+    /// 字节范围与字符集的交集
+    fn range(&self, lo: u8, hi: u8) -> u64 {
+        (lo..=hi).fold(0, |m, c| m | self.bits(c))
+    }
+
+    fn alt(&mut self) -> Result<Node, String> {
+        let mut v = vec![self.cat()?];
+        while self.eat(b'|') { v.push(self.cat()?); }
+        Ok(if v.len() == 1 { v.pop().unwrap() } else { Node::Alt(v) })
+    }
+
+    fn cat(&mut self) -> Result<Node, String> {
+        let mut v = vec![];
+        while let Some(c) = self.peek() {
+            if c == b'|' || c == b')' { break; }
+            let atom = self.atom()?;
+            v.push(self.quant(atom)?);
+        }
+        Ok(Node::Cat(v))
+    }
+
+    fn atom(&mut self) -> Result<Node, String> {
+        let c = self.peek().unwrap();
+        self.pos += 1;
+        match c {
+            b'.' => Ok(Node::Set(self.all)),
+            b'[' => self.class(),
+            b'(' => {
+                let id = self.groups.len();
+                self.groups.push(None);
+                let inner = self.alt()?;
+                if !self.eat(b')') { return Err("缺少 ')'".into()); }
+                // 记录单字符分组，供反向引用展开
+                if let Node::Cat(v) = &inner {
+                    if let [Node::Set(m)] = v.as_slice() { self.groups[id] = Some(*m); }
+                }
+                Ok(Node::Group(id, Box::new(inner)))
+            }
+            b'\\' => self.escape(false).map(|r| r.unwrap_or_else(Node::Ref)),
+            b'^' | b'$' => Err("^ 和 $ 只能出现在模式首尾".into()),
+            b'*' | b'+' | b'?' | b'{' => Err(format!("'{}' 前面缺少内容", c as char)),
+            _ => Ok(Node::Set(self.sym(c)?)),
+        }
+    }
+
+    /// `\d`、转义字面量，或反向引用 `\1`..`\9`（内层 Err 为分组下标）
+    fn escape(&mut self, in_class: bool) -> Result<Result<Node, usize>, String> {
+        let c = self.peek().ok_or("模式以 '\\' 结尾")?;
+        self.pos += 1;
+        match c {
+            b'd' => Ok(Ok(Node::Set(self.range(b'0', b'9')))),
+            b'1'..=b'9' if !in_class => Ok(Err((c - b'1') as usize)),
+            _ => Ok(Ok(Node::Set(self.sym(c)?))),
+        }
+    }
+
+    fn class(&mut self) -> Result<Node, String> {
+        let neg = self.eat(b'^');
+        let mut m = 0u64;
+        loop {
+            let c = self.peek().ok_or("缺少 ']'")?;
+            self.pos += 1;
+            match c {
+                b']' => break,
+                b'\\' => if let Ok(Node::Set(s)) = self.escape(true)? { m |= s },
+                _ if self.peek() == Some(b'-') && self.s.get(self.pos + 1).is_some_and(|&d| d != b']') => {
+                    let hi = self.s[self.pos + 1];
+                    self.pos += 2;
+                    if hi < c { return Err(format!("字符范围 {}-{} 无效", c as char, hi as char)); }
+                    m |= self.range(c, hi);
+                }
+                _ => m |= self.range(c, c),
+            }
+        }
+        let m = if neg { self.all & !m } else { m };
+        if m == 0 { return Err("字符类与字符集没有交集".into()); }
+        Ok(Node::Set(m))
+    }
+
+    fn num(&mut self) -> Option<u32> {
+        let st = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) { self.pos += 1; }
+        std::str::from_utf8(&self.s[st..self.pos]).ok()?.parse().ok()
+    }
+
+    fn quant(&mut self, mut atom: Node) -> Result<Node, String> {
+        loop {
+            let (lo, hi) = match self.peek() {
+                Some(b'*') => (0, None),
+                Some(b'+') => (1, None),
+                Some(b'?') => (0, Some(1)),
+                Some(b'{') => {
+                    self.pos += 1;
+                    let lo = self.num().ok_or("'{' 后需要数字")?;
+                    let hi = if self.eat(b',') {
+                        if self.peek() == Some(b'}') { None } else { Some(self.num().ok_or("'{n,' 后需要数字或 '}'")?) }
+                    } else { Some(lo) };
+                    if self.peek() != Some(b'}') { return Err("缺少 '}'".into()); }
+                    if hi.is_some_and(|h| h < lo) || lo.max(hi.unwrap_or(0)) > MAX_REP {
+                        return Err("量词次数无效".into());
+                    }
+                    (lo, hi)
+                }
+                _ => return Ok(atom),
+            };
+            self.pos += 1;
+            atom = Node::Rep(Box::new(atom), lo, hi);
+        }
+    }
+}
+
+/// 反向引用 `\n` 换成第 n 组可能取到的每个字符，整体展开为多个分支
+fn expand(root: Node, groups: &[Option<u64>]) -> Result<Node, String> {
+    let mut refs = vec![];
+    collect_refs(&root, &mut refs);
+    refs.sort_unstable();
+    refs.dedup();
+
+    let mut alts = vec![root];
+    for g in refs {
+        let m = groups.get(g).copied().flatten().ok_or("反向引用只支持单字符分组，如 (.)\\1{5}")?;
+        if alts.len() * m.count_ones() as usize > MAX_COPIES { return Err("模式过于复杂".into()); }
+        alts = alts.iter()
+            .flat_map(|n| (0..64).filter(move |k| m >> k & 1 == 1).map(move |k| subst(n, g, 1 << k)))
+            .collect();
+    }
+    Ok(if alts.len() == 1 { alts.pop().unwrap() } else { Node::Alt(alts) })
+}
+
+fn collect_refs(n: &Node, out: &mut Vec<usize>) {
+    match n {
+        Node::Ref(g) => out.push(*g),
+        Node::Cat(v) | Node::Alt(v) => v.iter().for_each(|x| collect_refs(x, out)),
+        Node::Rep(x, _, _) | Node::Group(_, x) => collect_refs(x, out),
+        Node::Set(_) => {}
+    }
+}
+
+fn subst(n: &Node, g: usize, m: u64) -> Node {
+    match n {
+        Node::Ref(r) | Node::Group(r, _) if *r == g => Node::Set(m),
+        Node::Cat(v) => Node::Cat(v.iter().map(|x| subst(x, g, m)).collect()),
+        Node::Alt(v) => Node::Alt(v.iter().map(|x| subst(x, g, m)).collect()),
+        Node::Rep(x, lo, hi) => Node::Rep(Box::new(subst(x, g, m)), *lo, *hi),
+        Node::Group(r, x) => Node::Group(*r, Box::new(subst(x, g, m))),
+        _ => n.clone(),
+    }
+}
+
+/// Thompson NFA 状态
+enum St { Sym(u64, usize), Split(usize, usize), Match }
+
+struct Nfa { st: Vec<St> }
+
+impl Nfa {
+    fn push(&mut self, s: St) -> Result<usize, String> {
+        if self.st.len() >= MAX_STATES { return Err("模式过于复杂".into()); }
+        self.st.push(s);
+        Ok(self.st.len() - 1)
+    }
+
+    /// 编译 `n`，匹配完后接着走 `next`，返回入口状态
+    fn build(&mut self, n: &Node, next: usize) -> Result<usize, String> {
+        Ok(match n {
+            Node::Set(m) => self.push(St::Sym(*m, next))?,
+            Node::Cat(v) => {
+                let mut at = next;
+                for x in v.iter().rev() { at = self.build(x, at)?; }
+                at
+            }
+            Node::Alt(v) => {
+                let mut at = self.build(&v[v.len() - 1], next)?;
+                for x in v[..v.len() - 1].iter().rev() {
+                    let s = self.build(x, next)?;
+                    at = self.push(St::Split(s, at))?;
+                }
+                at
+            }
+            Node::Rep(x, lo, hi) => {
+                let mut at = match hi {
+                    None => {
+                        // 循环: 占位后回填
+                        let l = self.push(St::Split(0, next))?;
+                        let body = self.build(x, l)?;
+                        self.st[l] = St::Split(body, next);
+                        l
+                    }
+                    Some(hi) => {
+                        let mut at = next;
+                        for _ in *lo..*hi {
+                            let body = self.build(x, at)?;
+                            at = self.push(St::Split(body, next))?;
+                        }
+                        at
+                    }
+                };
+                for _ in 0..*lo { at = self.build(x, at)?; }
+                at
+            }
+            Node::Group(_, x) => self.build(x, next)?,
+            Node::Ref(_) => unreachable!("反向引用已展开"),
+        })
+    }
+
+    /// ε 闭包，只保留字符状态和接受状态
+    fn closure(&self, from: &[usize], out: &mut Vec<usize>) {
+        let mut stack = from.to_vec();
+        let mut seen = vec![false; self.st.len()];
+        out.clear();
+        while let Some(s) = stack.pop() {
+            if std::mem::replace(&mut seen[s], true) { continue; }
+            match self.st[s] {
+                St::Split(a, b) => { stack.push(a); stack.push(b); }
+                _ => out.push(s),
+            }
+        }
+        out.sort_unstable();
+    }
+}
+
+/// 编译好的模式
+#[derive(Clone, Debug)]
+pub struct Pattern {
+    /// 字节 → 符号
+    map: [u8; 256],
+    syms: usize,
+    trans: Vec<u32>,
+    accept: Vec<bool>,
+    start: u32,
+    /// 以 `$` 结尾: 只在字符串末尾判断接受
+    end: bool,
+    prob: f64,
+}
+
+impl Pattern {
+    /// 编译模式；字符不在字符集中、语法错误或该地址类型下不可能匹配时返回原因
+    pub fn compile(pat: &str, s: &Settings) -> Result<Pattern, String> {
+        let pat = if is_bech32(s.addr_type) { pat.to_lowercase() } else { pat.to_string() };
+        let model = model(s);
+
+        // 字母表: 地址中可能出现的全部字符
+        let mut map = [NONE; 256];
+        let mut syms = 0usize;
+        for c in model.iter().flat_map(|(_, m)| m.iter()).flat_map(|p| p.bytes()) {
+            if map[c as usize] == NONE { map[c as usize] = syms as u8; syms += 1; }
+        }
+        let all = if syms == 64 { u64::MAX } else { (1 << syms) - 1 };
+
+        let mut body = pat.as_bytes();
+        let begin = body.first() == Some(&b'^');
+        if begin { body = &body[1..]; }
+        let end = body.last() == Some(&b'$') && !body.ends_with(b"\\$");
+        if end { body = &body[..body.len() - 1]; }
+        if body.is_empty() { return Err("模式为空".into()); }
+
+        let fold = s.ignore_case && !is_bech32(s.addr_type);
+        let mut p = Parser { s: body, pos: 0, map: &map, all, groups: vec![], fold };
+        let root = p.alt()?;
+        if p.pos < body.len() { return Err(format!("多余的 '{}'", body[p.pos] as char)); }
+        let root = expand(root, &p.groups)?;
+
+        let mut nfa = Nfa { st: vec![] };
+        let fin = nfa.push(St::Match)?;
+        let entry = nfa.build(&root, fin)?;
+
+        let (trans, accept, start) = determinize(&nfa, entry, syms, begin, end)?;
+        let mut pat = Pattern { map, syms, trans, accept, start, end, prob: 0.0 };
+        pat.prob = model.iter().map(|(w, m)| w * pat.estimate(m)).sum();
+        if pat.prob <= 0.0 { return Err("该地址类型下不可能匹配".into()); }
+        Ok(pat)
+    }
+
+    pub fn is_match(&self, addr: &str) -> bool {
+        let mut st = self.start as usize;
+        for &b in addr.as_bytes() {
+            let k = self.map[b as usize];
+            if k == NONE { return false; }
+            st = self.trans[st * self.syms + k as usize] as usize;
+            if !self.end && self.accept[st] { return true; }
+        }
+        self.accept[st]
+    }
+
+    /// 随机地址命中的概率
+    pub fn prob(&self) -> f64 { self.prob }
+
+    /// 期望尝试次数
+    pub fn expected(&self) -> u64 { (1.0 / self.prob).round().max(1.0) as u64 }
+
+    /// 按地址模型逐位置推进状态分布
+    fn estimate(&self, model: &[String]) -> f64 {
+        let mut dist = vec![0f64; self.accept.len()];
+        dist[self.start as usize] = 1.0;
+        for pos in model {
+            let w = 1.0 / pos.len() as f64;
+            let mut next = vec![0f64; dist.len()];
+            for (st, &p) in dist.iter().enumerate().filter(|(_, p)| **p > 0.0) {
+                for c in pos.bytes() {
+                    let k = self.map[c as usize] as usize;
+                    next[self.trans[st * self.syms + k] as usize] += p * w;
+                }
+            }
+            dist = next;
+        }
+        dist.iter().zip(&self.accept).filter(|(_, &a)| a).map(|(p, _)| p).sum()
+    }
+}
+
+/// 子集构造。状态 0 为死状态；未以 `$` 结尾时接受状态吸收（一旦命中即停）
+fn determinize(nfa: &Nfa, entry: usize, syms: usize, begin: bool, end: bool)
+    -> Result<(Vec<u32>, Vec<bool>, u32), String> {
+    let mut init = vec![];
+    nfa.closure(&[entry], &mut init);
+    let is_acc = |set: &[usize]| set.iter().any(|&s| matches!(nfa.st[s], St::Match));
+
+    let mut ids: HashMap<Vec<usize>, u32> = HashMap::new();
+    let mut sets: Vec<Vec<usize>> = vec![];
+    let mut work = 0;
+    let mut add = |set: Vec<usize>, sets: &mut Vec<Vec<usize>>| -> Result<u32, String> {
+        if let Some(&id) = ids.get(&set) { return Ok(id); }
+        work += set.len();
+        if sets.len() >= MAX_STATES || work > MAX_WORK { return Err("模式过于复杂".to_string()); }
+        let id = sets.len() as u32;
+        ids.insert(set.clone(), id);
+        sets.push(set);
+        Ok(id)
+    };
+
+    add(vec![], &mut sets)?;
+    let start = add(init.clone(), &mut sets)?;
+    let (mut trans, mut accept) = (vec![], vec![]);
+    let (mut from, mut next) = (vec![], vec![]);
+    let mut i = 0;
+    while i < sets.len() {
+        let set = sets[i].clone();
+        let acc = is_acc(&set);
+        accept.push(acc);
+        for k in 0..syms {
+            // 吸收态与（以 ^ 开头时的）死状态停在原地
+            if (acc && !end) || (set.is_empty() && begin) {
+                trans.push(i as u32);
+                continue;
+            }
+            from.clear();
+            from.extend(set.iter().filter_map(|&s| match nfa.st[s] {
+                St::Sym(m, t) if m >> k & 1 == 1 => Some(t),
+                _ => None,
+            }));
+            // 未以 ^ 开头: 每个位置都可以重新开始匹配
+            if !begin { from.push(entry); }
+            nfa.closure(&from, &mut next);
+            trans.push(add(next.clone(), &mut sets)?);
+        }
+        i += 1;
+    }
+    Ok((trans, accept, start))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Settings;
+    use rand::Rng;
+
+    /// 用小字母表拼出地址形状的字符串，让各模式有足够多的命中与不命中
+    fn samples(head: &str, alphabet: &[u8], len: usize, n: usize) -> Vec<String> {
+        let mut rng = rand::thread_rng();
+        (0..n).map(|_| {
+            let body: String = (0..len).map(|_| alphabet[rng.gen_range(0..alphabet.len())] as char).collect();
+            format!("{}{}", head, body)
+        }).collect()
+    }
+
+    /// DFA 与参照实现对每个样本的判断一致，且样本中既有命中也有不命中
+    fn check(s: &Settings, pats: &[&str], xs: &[String], flags: &str) {
+        for pat in pats {
+            let dfa = Pattern::compile(pat, s).unwrap_or_else(|e| panic!("{}: {}", pat, e));
+            let re = fancy_regex::Regex::new(&format!("{}{}", flags, pat)).unwrap();
+            let mut hits = 0;
+            for x in xs {
+                let want = re.is_match(x).unwrap();
+                assert_eq!(dfa.is_match(x), want, "{} / {}", pat, x);
+                hits += usize::from(want);
+            }
+            assert!(hits > 0 && hits < xs.len(), "{}: {}/{} 命中，样本没有区分度", pat, hits, xs.len());
+        }
+    }
+
+    #[test]
+    fn bech32_matches_reference() {
+        let s = Settings { addr_type: Addr::SegWit, ..Settings::default() };
+        let xs = samples("bc1q", b"qpzr0234", 38, 3000);
+        let pats = [
+            // 锚定
+            "^bc1q[02]", "^bc1qq*p", "[^0-9]{3}$", "0$", "^bc1q.{3}z",
+            // 字符类与量词
+            "[q-z]{4}", "\\d{4}", "[^qp]{5}", "q.?p+z", "z{2,3}0", "(qp){2,}",
+            // 分组与选择
+            "(qp|zr)+0$", "^bc1q(q|p)(r|z)", "(02|20|34)[pq]$", "p(q|zz|r0)p",
+            // 反向引用
+            "(.)\\1{3}", "(.)\\1{2}$", "^bc1q(.)\\1", "([qp])z\\1", "(\\d)\\1.\\1",
+        ];
+        check(&s, &pats, &xs, "");
+    }
+
+    #[test]
+    fn base58_matches_reference() {
+        let s = Settings { addr_type: Addr::Legacy, ..Settings::default() };
+        let xs = samples("1", b"aAbB12Cc", 33, 3000);
+        check(&s, &["^1[aA]", "(.)\\1{2}", "[a-c]{3}$", "(ab|BA)1", "\\d\\d[^a-z]"], &xs, "");
+        // 忽略大小写时字面字符两种写法都匹配
+        let s = Settings { ignore_case: true, ..s };
+        check(&s, &["^1ab", "ca$", "(ab|c1)b"], &xs, "(?i)");
+    }
+
+    #[test]
+    fn rejects_bad_patterns() {
+        let s = Settings { addr_type: Addr::SegWit, ..Settings::default() };
+        // 字符集以外的字符、语法错误、反向引用多字符分组
+        for p in ["o", "[q", "(qp", "q{3,1}", "(qp)\\1", "^$"] {
+            assert!(Pattern::compile(p, &s).is_err(), "{}", p);
+        }
+        // 固定前缀之外不可能出现
+        assert!(Pattern::compile("^bc1p", &s).is_err());
+    }
+}
@@ -0,0 +1,218 @@
+//! 美观评分: 不指定目标，按重复、循环、对称和顺序给地址打分
+//!
+//! 分数的单位是"位": 一个特征在固定位置出现的概率为 `base^-k` 时记 k 分，出现在中间任意位置时扣掉
+//! 可出现位置数的对数，所以单个特征的分数相当于同样难得的靓号位数。特征种类和位置很多，各项还会累加，
+//! 实测达到 k 分的地址大约和指定 k-1 位的靓号一样常见。
+//! 只看固定前缀（`bc1q` / `1` 等）之后的部分，含校验和；忽略大小写时按同一字符比较。
+
+use crate::addr::{base, is_bech32, pfx_len};
+use crate::config::Settings;
+use std::fmt;
+
+/// 低于这个分数的特征在随机地址里太常见，不计分
+const MIN_PART: f64 = 1.5;
+/// 检查的循环节最大长度
+const MAX_PERIOD: usize = 6;
+
+/// 特征种类
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Feature {
+    /// 连续相同字符，如 `qqqqqq`
+    Run,
+    /// 片段循环，如 `abab`、`123123`
+    Repeat,
+    /// 回文，如 `x7a9a7x`
+    Palindrome,
+    /// 按字母或数字顺序递增 / 递减，如 `2345`、`fedc`
+    Sequence,
+}
+
+/// 一个计分的特征: 在地址中从 `at` 开始的 `text`
+#[derive(Clone, Debug)]
+pub struct Part {
+    pub feature: Feature,
+    pub text: String,
+    pub at: usize,
+    pub value: f64,
+    /// 紧接固定前缀 (true) 或在地址末尾 (false)
+    pub edge: Option<bool>,
+}
+
+/// 地址的总分及各项来源，按分值从高到低
+#[derive(Clone, Debug, Default)]
+pub struct Score { pub total: f64, pub parts: Vec<Part> }
+
+impl fmt::Display for Part {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let n = self.text.chars().count();
+        let what = match self.feature {
+            Feature::Run => format!("连续 {} 个相同字符", n),
+            Feature::Repeat => format!("{} 位循环", n),
+            Feature::Palindrome => format!("{} 位回文", n),
+            Feature::Sequence => format!("{} 位顺序", n),
+        };
+        let at = match self.edge {
+            Some(true) => "开头".to_string(),
+            Some(false) => "结尾".to_string(),
+            None => format!("第 {} 位起", self.at + 1),
+        };
+        write!(f, "{} {} ({}) +{:.1}", what, self.text, at, self.value)
+    }
+}
+
+impl Score {
+    /// 每项一行的说明
+    pub fn explain(&self) -> Vec<String> { self.parts.iter().map(|p| p.to_string()).collect() }
+}
+
+/// 给地址打分: 找出全部候选特征，按分值从高到低选取互不重叠的计入总分
+pub fn score(addr: &str, s: &Settings) -> Score {
+    let skip = pfx_len(s.addr_type, s.network);
+    let fold = s.ignore_case && !is_bech32(s.addr_type);
+    let key = if fold { addr.to_lowercase() } else { addr.to_string() };
+    let b = &key.as_bytes()[skip.min(key.len())..];
+    let n = b.len();
+    let lb = (base(s.addr_type) as f64).ln();
+    // 中间任意位置出现时扣掉可出现位置数的对数；开头结尾位置固定，不扣
+    let spot = |i: usize, len: usize| -> (f64, Option<bool>) {
+        if i == 0 { (0.0, Some(true)) } else if i + len == n { (0.0, Some(false)) } else { (((n - len + 1) as f64).ln() / lb, None) }
+    };
+    let mut cands: Vec<(usize, usize, Feature, f64)> = vec![];
+
+    // 连续相同字符；与固定前缀的最后一个字符相同时（如 bc1q 之后的 qqqq）看起来更长，多记 1 位
+    let tail = skip.checked_sub(1).and_then(|i| key.as_bytes().get(i)).copied();
+    for (i, len) in spans(n, |j| b[j] == b[j - 1]) {
+        if len < 3 { continue; }
+        let bonus = if i == 0 && tail == Some(b[0]) { 1.0 } else { 0.0 };
+        cands.push((i, len, Feature::Run, (len - 1) as f64 + bonus - spot(i, len).0));
+    }
+    // 循环节长度 k: 第 j 位与第 j-k 位相同；整段都是同一字符的算连续字符
+    for k in 2..=MAX_PERIOD.min(n / 2) {
+        for (i, len) in spans(n, |j| j >= k && b[j] == b[j - k]) {
+            // 段内第一位之后的字符都与前一个循环节相同，整段从第一个循环节开始
+            let (i, len) = (i + 1 - k, len - 1 + k);
+            if len < 2 * k || len < 4 || b[i..i + len].iter().all(|&c| c == b[i]) { continue; }
+            cands.push((i, len, Feature::Repeat, (len - k) as f64 - spot(i, len).0));
+        }
+    }
+    // 回文: 以每个位置（或两位置之间）为中心向外扩展
+    for c in 0..(2 * n).saturating_sub(1) {
+        let (mut l, mut r) = (c / 2, c / 2 + c % 2);
+        if r >= n || b[l] != b[r] { continue; }
+        while l > 0 && r + 1 < n && b[l - 1] == b[r + 1] { l -= 1; r += 1; }
+        let len = r - l + 1;
+        if len < 5 || b[l..=r].iter().all(|&c| c == b[l]) { continue; }
+        cands.push((l, len, Feature::Palindrome, (len / 2) as f64 - spot(l, len).0));
+    }
+    // 顺序: 相邻字符按 ASCII 差 1（同为数字或同为字母），方向两种
+    for d in [1i16, -1] {
+        let step = |j: usize| b[j] as i16 - b[j - 1] as i16 == d && b[j].is_ascii_digit() == b[j - 1].is_ascii_digit();
+        for (i, len) in spans(n, step) {
+            if len < 3 { continue; }
+            cands.push((i, len, Feature::Sequence, (len - 1) as f64 - 2f64.ln() / lb - spot(i, len).0));
+        }
+    }
+
+    cands.retain(|c| c.3 >= MIN_PART);
+    cands.sort_by(|a, b| b.3.total_cmp(&a.3).then(a.0.cmp(&b.0)));
+    let mut used = vec![false; n];
+    let mut out = Score::default();
+    for (i, len, feature, value) in cands {
+        if used[i..i + len].iter().any(|&u| u) { continue; }
+        used[i..i + len].iter_mut().for_each(|u| *u = true);
+        out.total += value;
+        out.parts.push(Part { feature, text: addr[skip + i..skip + i + len].to_string(), at: skip + i, value, edge: spot(i, len).1 });
+    }
+    out
+}
+
+/// `ok(j)` 表示第 j 位延续了前一位的关系；返回各最长段的起点与长度（含起点那一位）
+fn spans(n: usize, ok: impl Fn(usize) -> bool) -> Vec<(usize, usize)> {
+    let mut out = vec![];
+    let mut i = 0;
+    while i < n {
+        let mut j = i + 1;
+        while j < n && ok(j) { j += 1; }
+        if j - i > 1 { out.push((i, j - i)); }
+        i = j;
+    }
+    out
+}
+
+/// 达到 `min` 分的粗略期望尝试次数，取实测的 `base^(min-1)`
+pub fn expected(min: f64, s: &Settings) -> u64 {
+    (base(s.addr_type) as f64).powf(min - 1.0).round().clamp(1.0, u64::MAX as f64) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::addr::{charset, Addr};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    /// 随机的 bc1q 地址（只看字符，不需要合法校验和）
+    fn random(rng: &mut impl Rng) -> String {
+        let cs = charset(Addr::SegWit).as_bytes();
+        "bc1q".chars().chain((0..38).map(|_| cs[rng.gen_range(0..cs.len())] as char)).collect()
+    }
+
+    /// 在地址中间第 `at` 位（固定前缀之后）起放入 `text`
+    fn plant(addr: &str, at: usize, text: &str) -> String {
+        format!("{}{}{}", &addr[..4 + at], text, &addr[4 + at + text.len()..])
+    }
+
+    #[test]
+    fn features_outrank_random() {
+        let s = Settings { addr_type: Addr::SegWit, ..Settings::default() };
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut plain: Vec<f64> = (0..2000).map(|_| score(&random(&mut rng), &s).total).collect();
+        plain.sort_by(f64::total_cmp);
+        let p99 = plain[plain.len() * 99 / 100];
+        for (text, feature) in [
+            ("7777777", Feature::Run),
+            ("acacacac", Feature::Repeat),
+            ("x7a9g9a7x", Feature::Palindrome),
+            ("2345678", Feature::Sequence),
+            ("hgfedc", Feature::Sequence),
+        ] {
+            for _ in 0..50 {
+                let addr = plant(&random(&mut rng), 15, text);
+                let sc = score(&addr, &s);
+                assert!(sc.total > p99, "{} {:.1} <= {:.1}", addr, sc.total, p99);
+                let top = &sc.parts[0];
+                assert_eq!(top.feature, feature, "{} {:?}", addr, sc.parts);
+                assert!(top.text.contains(&text[1..text.len() - 1]), "{} {:?}", addr, top);
+            }
+        }
+    }
+
+    /// 含 `text` 的那一项
+    fn part(sc: &Score, text: &str) -> Part { sc.parts.iter().find(|p| p.text.contains(text)).unwrap().clone() }
+
+    #[test]
+    fn longer_and_edge_features_score_higher() {
+        let s = Settings { addr_type: Addr::SegWit, ..Settings::default() };
+        let mut rng = StdRng::seed_from_u64(11);
+        for _ in 0..50 {
+            let a = random(&mut rng);
+            let short = part(&score(&plant(&a, 15, "x55555x"), &s), "55555");
+            let long = part(&score(&plant(&a, 15, "x555555555x"), &s), "55555");
+            assert!(long.value > short.value);
+            // 开头位置固定，比中间同样长的更难得；与 bc1q 的 q 连成一片再多记 1 位
+            let edge = part(&score(&plant(&a, 0, "555555555x"), &s), "55555");
+            assert!(edge.value > long.value);
+            assert_eq!(edge.edge, Some(true));
+            let q = part(&score(&plant(&a, 0, "qqqqqqqqqx"), &s), "qqqqq");
+            assert!((q.value - edge.value - 1.0).abs() < 1e-9, "{:?} {:?}", q, edge);
+        }
+    }
+
+    #[test]
+    fn expected_grows_with_score() {
+        let s = Settings { addr_type: Addr::Legacy, ..Settings::default() };
+        assert_eq!(expected(1.0, &s), 1);
+        assert_eq!(expected(3.0, &s), 58 * 58);
+        assert!(expected(6.0, &s) > expected(5.5, &s));
+    }
+}
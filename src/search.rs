@@ -0,0 +1,725 @@
+//! 搜索引擎: 多线程生成地址并与目标比较
+
+use crate::addr::{deriv, pfx_len, Addr};
+use crate::config::{Collect, KeyGen, Limit, Match, Out, RngMode, Settings, Spec, Target};
+use crate::ec::{compress, Walker, LAMBDA};
+use crate::matcher::{Matcher, Near};
+use crate::odds::Odds;
+use crate::score::{self, Score};
+use crate::Error;
+use bip39::Mnemonic;
+use bitcoin::address::{Payload, WitnessProgram, WitnessVersion};
+use bitcoin::bip32::{DerivationPath, ExtendedPrivKey};
+use bitcoin::hashes::{hash160, Hash};
+use bitcoin::key::{TapTweak, TweakedPublicKey};
+use bitcoin::secp256k1::{Scalar, Secp256k1, SecretKey, Signing, Verification, XOnlyPublicKey};
+use bitcoin::{Address, Network, PrivateKey, PubkeyHash, ScriptBuf, ScriptHash, WPubkeyHash};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use rand_xoshiro::rand_core::SeedableRng;
+use rand_xoshiro::Xoshiro256PlusPlus;
+use std::borrow::Borrow;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as FmtWrite;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// 命中结果
+#[derive(Clone, Debug)]
+pub struct Found {
+    pub addr: String,
+    pub mnemonic: Option<String>,
+    pub wif: Option<String>,
+    pub target: String,
+    /// 助记词派生路径（仅助记词模式）
+    pub path: Option<String>,
+    /// 公钥 hex（Taproot 为 x-only 内部公钥）
+    pub pubkey: String,
+    /// 锁定脚本 hex
+    pub script_pubkey: String,
+    /// 因美观评分命中时的分数与说明，`target` 为其中分值最高的片段
+    pub score: Option<Score>,
+}
+
+/// 接近命中的地址: 匹配了目标 `found.target` 的 `matched` 位（共 `len` 位）
+#[derive(Clone, Debug)]
+pub struct Partial { pub found: Found, pub matched: usize, pub len: usize }
+
+impl Partial {
+    /// 排序依据: 匹配位数多的在前，同样多时差得少的在前
+    fn rank(&self) -> (Reverse<usize>, usize) { (Reverse(self.matched), self.len - self.matched) }
+}
+
+/// 近似命中榜保留的条数
+const BOARD: usize = 100;
+
+/// 搜索构建器
+///
+/// ```no_run
+/// use btc_vanity::{Addr, Collect, Searcher};
+///
+/// let found = Searcher::new()
+///     .addr_type(Addr::SegWit)
+///     .threads(4)
+///     .target("abc")
+///     .collect(Collect::Total(3))
+///     .run(|f| println!("{}", f.addr))
+///     .unwrap();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Searcher {
+    settings: Settings,
+    targets: Vec<Spec>,
+    prior: u64,
+}
+
+impl Searcher {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn with_settings(settings: Settings) -> Self {
+        Self { settings, targets: vec![], prior: 0 }
+    }
+
+    pub fn addr_type(mut self, a: Addr) -> Self { self.settings.addr_type = a; self }
+    pub fn network(mut self, n: Network) -> Self { self.settings.network = n; self }
+    pub fn match_mode(mut self, m: Match) -> Self { self.settings.match_mode = m; self }
+    pub fn output(mut self, o: Out) -> Self { self.settings.output = o; self }
+    pub fn rng(mut self, r: RngMode) -> Self { self.settings.rng_mode = r; self }
+    pub fn threads(mut self, n: usize) -> Self { self.settings.threads = n; self }
+    pub fn batch_size(mut self, n: u64) -> Self { self.settings.batch_size = n; self }
+    pub fn collect(mut self, c: Collect) -> Self { self.settings.collect = c; self }
+    pub fn keygen(mut self, k: KeyGen) -> Self { self.settings.keygen = k; self }
+    pub fn ignore_case(mut self, on: bool) -> Self { self.settings.ignore_case = on; self }
+    pub fn checksum(mut self, on: bool) -> Self { self.settings.checksum = on; self }
+    pub fn max_time(mut self, d: Duration) -> Self { self.settings.max_time = Some(d); self }
+    pub fn max_attempts(mut self, n: u64) -> Self { self.settings.max_attempts = Some(n); self }
+    pub fn max_prob(mut self, q: f64) -> Self { self.settings.max_prob = Some(q); self }
+    pub fn min_score(mut self, v: f64) -> Self { self.settings.min_score = Some(v); self }
+
+    /// 之前已对同一组目标做过的尝试次数（见 [`crate::ledger`]），计入概率上限
+    pub fn prior(mut self, n: u64) -> Self { self.prior = n; self }
+
+    pub fn target(mut self, s: impl Into<String>) -> Self {
+        self.targets.push(Spec::new(s));
+        self
+    }
+
+    pub fn targets<I, S>(mut self, it: I) -> Self
+    where I: IntoIterator<Item = S>, S: Into<String> {
+        self.targets.extend(it.into_iter().map(Spec::new));
+        self
+    }
+
+    /// 带单独匹配模式 / 优先级的目标（如来自目标文件）
+    pub fn specs(mut self, it: impl IntoIterator<Item = Spec>) -> Self {
+        self.targets.extend(it);
+        self
+    }
+
+    pub fn settings(&self) -> &Settings { &self.settings }
+
+    /// 按最终设置校验全部目标
+    pub fn resolve(&self) -> Result<Vec<Target>, Error> {
+        resolve(&self.settings, &self.targets)
+    }
+
+    /// 启动工作线程，立即返回句柄
+    pub fn spawn(self) -> Result<Handle, Error> {
+        // 边校验边释放目标描述，目标很多时不同时保留两份
+        let targets = resolve(&self.settings, self.targets)?;
+        let hits = Mutex::new(Collected { seen: HashSet::new(), per_target: HashMap::new(), total: 0 });
+        let (tx, rx) = mpsc::channel::<Found>();
+        let set = Set::new(&self.settings, targets, self.prior, 0, 0, None);
+        let t0 = Instant::now();
+        let shared = Arc::new(Shared {
+            deadline: Mutex::new(self.settings.max_time.map(|d| t0 + d)),
+            limit: Mutex::new(None),
+            cap: AtomicU64::new(set.budget.map_or(u64::MAX, |b| b.0)),
+            set: RwLock::new(Arc::new(set)),
+            gen: AtomicU64::new(0),
+            board: Mutex::new(vec![]),
+            bar: AtomicUsize::new(0),
+            active: AtomicUsize::new(self.settings.threads),
+            settings: self.settings,
+            stop: AtomicBool::new(false),
+            paused: AtomicBool::new(false),
+            cnt: AtomicU64::new(0),
+            hits,
+            tx: Mutex::new(Some(tx)),
+        });
+
+        let workers = (0..shared.settings.threads).filter_map(|id| start(&shared, id)).collect();
+        Ok(Handle { shared, t0, rx, workers, pause: Mutex::new((None, Duration::ZERO)) })
+    }
+
+    /// 阻塞运行直到收集完成，每个结果到达时调用回调
+    pub fn run<F: FnMut(&Found)>(self, mut f: F) -> Result<Vec<Found>, Error> {
+        let h = self.spawn()?;
+        let mut all = vec![];
+        while let Some(r) = h.recv() {
+            f(&r);
+            all.push(r);
+        }
+        h.join();
+        Ok(all)
+    }
+}
+
+fn resolve<S: Borrow<Spec>>(settings: &Settings, specs: impl IntoIterator<Item = S>) -> Result<Vec<Target>, Error> {
+    if settings.threads == 0 { return Err(Error::NoThreads); }
+    if settings.collect == Collect::Total(0) { return Err(Error::NoCount); }
+    let targets = specs.into_iter()
+        .map(|s| Target::parse(s.borrow(), settings))
+        .collect::<Result<Vec<_>, _>>()?;
+    if targets.is_empty() && settings.min_score.is_none() { return Err(Error::NoTargets); }
+    Ok(targets)
+}
+
+/// 已收集的结果（用于去重与判断是否完成）；按目标计数的键为 (匹配模式, 目标)，评分命中为 None，
+/// 替换目标集合后仍保留的目标计数不变
+struct Collected {
+    seen: HashSet<String>,
+    per_target: HashMap<Option<(Match, String)>, u64>,
+    total: u64,
+}
+
+fn slot(t: &Target) -> Option<(Match, String)> { Some((t.mode, t.raw.clone())) }
+
+/// 一组目标及由它生成的匹配器；搜索中替换目标时整体换掉
+struct Set {
+    /// 第几次替换，工作线程据此发现新的集合
+    gen: u64,
+    targets: Vec<Target>,
+    matcher: Matcher,
+    near: Option<Near>,
+    /// 各目标目前最多匹配的位数；既不刷新它也进不了榜的候选不必加锁
+    floors: Vec<AtomicU8>,
+    /// 尝试次数上限及其来源（次数或概率）
+    budget: Option<(u64, Limit)>,
+}
+
+impl Set {
+    /// `prior` 为之前对这组目标已做的尝试次数，计入概率上限；`done` 为换成这组目标时本次已做的尝试次数，
+    /// 上限从这里往后算。`old` 中仍保留的目标沿用其最多匹配位数
+    fn new(s: &Settings, targets: Vec<Target>, prior: u64, done: u64, gen: u64, old: Option<&Set>) -> Set {
+        let matcher = Matcher::new(s, &targets);
+        let near = Near::new(s, &targets, &matcher);
+        // 概率上限换算成尝试次数，与次数上限取较小者
+        let by_prob = s.max_prob.map(|q| {
+            let exps = targets.iter().map(|t| t.expected(s)).chain(s.min_score.map(|m| score::expected(m, s)));
+            let need = Odds::new(exps).attempts_for(q);
+            ((need - prior as f64).max(0.0).min(u64::MAX as f64) as u64 + done, Limit::Probability)
+        });
+        let budget = [s.max_attempts.map(|n| (n, Limit::Attempts)), by_prob].into_iter().flatten().min_by_key(|b| b.0);
+        let floors = if near.is_none() { vec![] } else {
+            let was: HashMap<Option<(Match, String)>, u8> = old.into_iter()
+                .flat_map(|o| o.targets.iter().zip(&o.floors).map(|(t, f)| (slot(t), f.load(Ordering::Relaxed))))
+                .collect();
+            targets.iter().map(|t| AtomicU8::new(was.get(&slot(t)).copied().unwrap_or(0))).collect()
+        };
+        Set { gen, targets, matcher, near, floors, budget }
+    }
+}
+
+/// 工作线程共享状态
+struct Shared {
+    settings: Settings,
+    stop: AtomicBool,
+    /// 暂停时所有线程在批次之间等待
+    paused: AtomicBool,
+    /// 当前启用的线程数，编号不小于它的线程等待
+    active: AtomicUsize,
+    cnt: AtomicU64,
+    hits: Mutex<Collected>,
+    /// 当前的目标集合；替换时 `gen` 加一，工作线程在批次之间发现后换用新的
+    set: RwLock<Arc<Set>>,
+    gen: AtomicU64,
+    /// 当前集合的尝试次数上限，每次检查都读，不必去锁 `set`
+    cap: AtomicU64,
+    /// 新线程从这里取发送端；停止时丢弃，所有线程退出后接收端随之断开
+    tx: Mutex<Option<Sender<Found>>>,
+    /// 到时停止的时刻，每次暂停后顺延暂停的时长
+    deadline: Mutex<Option<Instant>>,
+    /// 因哪个上限停止
+    limit: Mutex<Option<Limit>>,
+    /// 近似命中榜: 匹配位数最多的若干地址，已排好序；`bar` 为榜满时最后一名的位数
+    board: Mutex<Vec<Partial>>,
+    bar: AtomicUsize,
+}
+
+/// 候选检查结果: 命中目标，美观评分达标，或只匹配了目标的若干位且比目前最好的更多
+enum Probe { Hit(Address, usize), Pretty(Address, Score), Near(Address, Vec<(usize, usize)>) }
+
+/// 启动编号为 `id` 的工作线程，已停止时返回 None
+fn start(shared: &Arc<Shared>, id: usize) -> Option<JoinHandle<()>> {
+    let tx = shared.tx.lock().unwrap().clone()?;
+    let shared = shared.clone();
+    Some(thread::spawn(move || worker(shared, tx, id)))
+}
+
+impl Shared {
+    fn set(&self) -> Arc<Set> { self.set.read().unwrap().clone() }
+
+    fn halt(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.tx.lock().unwrap().take();
+    }
+
+    /// 达到上限时记下原因并停止
+    fn over(&self) -> bool {
+        if self.stop.load(Ordering::Relaxed) { return false; }
+        let by_time = self.deadline.lock().unwrap().is_some_and(|d| Instant::now() >= d).then_some(Limit::Time);
+        let by_cnt = (self.cnt.load(Ordering::Relaxed) >= self.cap.load(Ordering::Relaxed))
+            .then(|| self.set().budget.map_or(Limit::Attempts, |b| b.1));
+        let Some(l) = by_time.or(by_cnt) else { return false };
+        self.limit.lock().unwrap().get_or_insert(l);
+        self.halt();
+        true
+    }
+
+    /// 线程 `id` 是否应停下当前批次: 已停止、已暂停、超出当前线程数或达到上限
+    fn held(&self, id: usize) -> bool {
+        self.stop.load(Ordering::Relaxed) || self.paused.load(Ordering::Relaxed) || id >= self.active.load(Ordering::Relaxed)
+            || self.over()
+    }
+
+    /// 等到线程 `id` 可以继续，停止时返回 false
+    fn wait(&self, id: usize) -> bool {
+        while self.held(id) {
+            if self.stop.load(Ordering::Relaxed) { return false; }
+            thread::sleep(Duration::from_millis(50));
+        }
+        true
+    }
+
+    /// 登记一次命中（`idx` 为 `set` 中的目标下标，None 为评分命中），重复地址或已无需要的结果返回 false；
+    /// 达到收集目标时置停止标志
+    fn accept(&self, set: &Set, addr: &str, idx: Option<usize>) -> bool {
+        let key = idx.and_then(|i| slot(&set.targets[i]));
+        let mut c = self.hits.lock().unwrap();
+        if self.stop.load(Ordering::Relaxed) || c.seen.contains(addr) { return false; }
+        if self.settings.collect == Collect::PerTarget && c.per_target.contains_key(&key) { return false; }
+
+        c.seen.insert(addr.to_string());
+        *c.per_target.entry(key).or_default() += 1;
+        c.total += 1;
+        let done = match self.settings.collect {
+            Collect::Total(n) => c.total >= n,
+            Collect::PerTarget => set.targets.iter().all(|t| c.per_target.contains_key(&slot(t)))
+                && (self.settings.min_score.is_none() || c.per_target.contains_key(&None)),
+            Collect::Unlimited => false,
+        };
+        if done { self.halt(); }
+        true
+    }
+}
+
+/// 运行中的搜索
+pub struct Handle {
+    shared: Arc<Shared>,
+    t0: Instant,
+    rx: Receiver<Found>,
+    workers: Vec<JoinHandle<()>>,
+    /// 本次暂停开始的时间与之前暂停的总时长，不计入耗时
+    pause: Mutex<(Option<Instant>, Duration)>,
+}
+
+impl Handle {
+    pub fn settings(&self) -> &Settings { &self.shared.settings }
+    /// 当前的目标（替换后为新的一组）
+    pub fn targets(&self) -> Vec<Target> { self.shared.set().targets.clone() }
+
+    /// 搜索中替换全部目标: 校验后生成新的匹配器，工作线程在各自的下一批开始时换用。
+    /// 已做的尝试与已收集的结果保留；仍在的目标沿用其近似命中记录，已删除目标的近似命中从榜上去掉。
+    /// `prior` 同 [`Searcher::prior`]，按新的一组目标重算概率上限
+    pub fn set_targets(&self, specs: impl IntoIterator<Item = Spec>, prior: u64) -> Result<(), Error> {
+        let sh = &self.shared;
+        let targets = resolve(&sh.settings, specs)?;
+        let old = sh.set();
+        let set = Set::new(&sh.settings, targets, prior, self.attempts(), old.gen + 1, Some(&old));
+        {
+            let mut board = sh.board.lock().unwrap();
+            board.retain(|p| set.targets.iter().any(|t| t.raw == p.found.target));
+            sh.bar.store(if board.len() == BOARD { board[BOARD - 1].matched } else { 0 }, Ordering::Relaxed);
+        }
+        let (gen, budget) = (set.gen, set.budget.map_or(u64::MAX, |b| b.0));
+        *sh.set.write().unwrap() = Arc::new(set);
+        sh.gen.store(gen, Ordering::Relaxed);
+        sh.cap.store(budget, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// 已尝试次数（私钥模式每批汇报一次，助记词模式每次派生都汇报）
+    pub fn attempts(&self) -> u64 { self.shared.cnt.load(Ordering::Relaxed) }
+    /// 搜索耗时，不含暂停的时间
+    pub fn elapsed(&self) -> Duration {
+        let (since, total) = *self.pause.lock().unwrap();
+        self.t0.elapsed() - total - since.map_or(Duration::ZERO, |t| t.elapsed())
+    }
+
+    /// 已收集（去重后）的结果数
+    pub fn found(&self) -> u64 { self.shared.hits.lock().unwrap().total }
+
+    /// 因达到哪个上限而停止；未停止或手动停止时为 None
+    pub fn limit(&self) -> Option<Limit> { *self.shared.limit.lock().unwrap() }
+
+    /// 是否跟踪近似命中（模式目标、或预筛代价过高时不跟踪）
+    pub fn tracks_near(&self) -> bool { self.shared.set().near.is_some() }
+
+    /// 目前最接近的未命中地址（含密钥）
+    pub fn best(&self) -> Option<Partial> { self.board(1).pop() }
+
+    /// 近似命中榜前 `n` 名（含密钥，显示时应只给出地址）
+    pub fn board(&self, n: usize) -> Vec<Partial> {
+        self.shared.board.lock().unwrap().iter().take(n).cloned().collect()
+    }
+
+    /// 第 `i` 个目标目前最多匹配了几位
+    pub fn reach(&self, i: usize) -> usize {
+        self.shared.set().floors.get(i).map_or(0, |f| f.load(Ordering::Relaxed) as usize)
+    }
+
+    pub fn stop(&self) { self.shared.halt(); }
+    pub fn is_stopped(&self) -> bool { self.shared.stop.load(Ordering::Relaxed) }
+
+    /// 暂停或继续所有工作线程；计数与已收集的结果保留
+    pub fn set_paused(&self, on: bool) {
+        let mut p = self.pause.lock().unwrap();
+        match (on, p.0) {
+            (true, None) => p.0 = Some(Instant::now()),
+            (false, Some(t)) => {
+                let d = t.elapsed();
+                if let Some(end) = self.shared.deadline.lock().unwrap().as_mut() { *end += d; }
+                *p = (None, p.1 + d);
+            }
+            _ => {}
+        }
+        self.shared.paused.store(on, Ordering::Relaxed);
+    }
+
+    pub fn is_paused(&self) -> bool { self.shared.paused.load(Ordering::Relaxed) }
+
+    /// 当前启用的线程数
+    pub fn threads(&self) -> usize { self.shared.active.load(Ordering::Relaxed) }
+
+    /// 调整线程数（至少 1）: 多出的线程在批次之间等待，不够时启动新线程
+    pub fn set_threads(&mut self, n: usize) {
+        let n = n.max(1);
+        self.shared.active.store(n, Ordering::Relaxed);
+        for id in self.workers.len()..n {
+            match start(&self.shared, id) {
+                Some(h) => self.workers.push(h),
+                None => break,
+            }
+        }
+    }
+
+    /// 阻塞等待下一个结果，收集完成且所有线程退出后返回 None
+    pub fn recv(&self) -> Option<Found> { self.rx.recv().ok() }
+
+    pub fn recv_timeout(&self, d: Duration) -> Result<Found, RecvTimeoutError> {
+        self.rx.recv_timeout(d)
+    }
+
+    /// 停止并等待所有工作线程退出
+    pub fn join(self) {
+        self.stop();
+        for h in self.workers { let _ = h.join(); }
+    }
+}
+
+impl Shared {
+    /// 返回命中且登记成功的目标下标，同时命中多个时优先级高的在前
+    fn hit(&self, set: &Set, buf: &str, skip: usize) -> Option<usize> {
+        let mut ids = set.matcher.find(buf, skip);
+        if ids.len() > 1 { ids.sort_by_key(|&i| (Reverse(set.targets[i].priority), i)); }
+        ids.into_iter().find(|&i| self.accept(set, buf, Some(i)))
+    }
+
+    /// 检查一个压缩公钥: 先在原始数据上预筛，可能命中（或接近命中）时才编码成地址字符串
+    fn probe<C: Verification>(&self, set: &Set, secp: &Secp256k1<C>, pk: &[u8; 33], buf: &mut String, skip: usize) -> Option<Probe> {
+        let a = self.settings.addr_type;
+        let prog = Prog::new(secp, pk, a)?;
+        buf.clear();
+        // 评分没有预筛，每个候选都要编码
+        let maybe = set.matcher.maybe(prog.bytes(), buf) || self.settings.min_score.is_some();
+        let near = set.near.as_ref().filter(|n| n.maybe(prog.bytes(), buf));
+        if !maybe && near.is_none() { return None; }
+        let addr = prog.address(a, self.settings.network)?;
+        buf.clear();
+        write!(buf, "{}", addr).unwrap();
+        if let Some(idx) = self.hit(set, buf, skip) { return Some(Probe::Hit(addr, idx)); }
+        if let Some(min) = self.settings.min_score {
+            let sc = score::score(buf, &self.settings);
+            if sc.total >= min && self.accept(set, buf, None) { return Some(Probe::Pretty(addr, sc)); }
+        }
+        let better: Vec<_> = near?.find(buf, skip, &set.targets).into_iter()
+            .filter(|&(i, n)| n > set.floors[i].load(Ordering::Relaxed) as usize || n > self.bar.load(Ordering::Relaxed))
+            .collect();
+        (!better.is_empty()).then_some(Probe::Near(addr, better))
+    }
+
+    /// 命中的结果发给接收端，接近命中的记入各目标的近似命中榜
+    fn report<C: Signing>(&self, set: &Set, secp: &Secp256k1<C>, p: Probe, sk: SecretKey, mnemonic: Option<String>, tx: &Sender<Found>) {
+        match p {
+            Probe::Hit(addr, idx) => { let _ = tx.send(self.found(secp, &addr, sk, mnemonic, set.targets[idx].raw.clone())); }
+            Probe::Pretty(addr, sc) => {
+                let target = sc.parts.first().map_or(String::new(), |p| p.text.clone());
+                let _ = tx.send(Found { score: Some(sc), ..self.found(secp, &addr, sk, mnemonic, target) });
+            }
+            Probe::Near(addr, ids) => {
+                let found = self.found(secp, &addr, sk, mnemonic, String::new());
+                let mut board = self.board.lock().unwrap();
+                for (idx, n) in ids {
+                    set.floors[idx].fetch_max(n.min(u8::MAX as usize) as u8, Ordering::Relaxed);
+                    let t = &set.targets[idx];
+                    let p = Partial { found: Found { target: t.raw.clone(), ..found.clone() }, matched: n, len: t.fixed_len() };
+                    // 同样好的先到先排
+                    let at = board.partition_point(|b| b.rank() <= p.rank());
+                    if at < BOARD { board.insert(at, p); }
+                }
+                board.truncate(BOARD);
+                if board.len() == BOARD { self.bar.store(board[BOARD - 1].matched, Ordering::Relaxed); }
+            }
+        }
+    }
+
+    /// 按输出设置组装命中结果
+    fn found<C: Signing>(&self, secp: &Secp256k1<C>, addr: &Address, sk: SecretKey, mnemonic: Option<String>, target: String) -> Found {
+        let s = &self.settings;
+        let wif = if s.output == Out::Wif || s.output == Out::Both {
+            Some(PrivateKey::new(sk, s.network).to_wif())
+        } else { None };
+        let pubkey = match s.addr_type {
+            Addr::Taproot => sk.x_only_public_key(secp).0.to_string(),
+            _ => sk.public_key(secp).to_string(),
+        };
+        Found {
+            addr: addr.to_string(),
+            path: mnemonic.as_ref().map(|_| deriv(s.addr_type, s.network).to_string()),
+            mnemonic: if s.output == Out::Mnemonic || s.output == Out::Both { mnemonic } else { None },
+            wif,
+            target,
+            pubkey,
+            script_pubkey: addr.script_pubkey().to_hex_string(),
+            score: None,
+        }
+    }
+}
+
+/// 地址的原始数据: hash160 或 Taproot 输出公钥
+enum Prog { Hash([u8; 20]), Taproot(TweakedPublicKey, [u8; 32]) }
+
+impl Prog {
+    /// 由压缩公钥计算原始数据，不做字符串编码
+    fn new<C: Verification>(secp: &Secp256k1<C>, pk: &[u8; 33], a: Addr) -> Option<Prog> {
+        Some(match a {
+            Addr::Taproot => {
+                let (tk, _) = XOnlyPublicKey::from_slice(&pk[1..]).ok()?.tap_tweak(secp, None);
+                Prog::Taproot(tk, tk.serialize())
+            }
+            Addr::SegWit | Addr::Legacy => Prog::Hash(hash160::Hash::hash(pk).to_byte_array()),
+            Addr::P2SH => {
+                let script = ScriptBuf::new_v0_p2wpkh(&WPubkeyHash::hash(pk));
+                Prog::Hash(ScriptHash::hash(script.as_bytes()).to_byte_array())
+            }
+        })
+    }
+
+    fn bytes(&self) -> &[u8] {
+        match self { Prog::Hash(h) => h, Prog::Taproot(_, b) => b }
+    }
+
+    fn address(&self, a: Addr, net: Network) -> Option<Address> {
+        let payload = match (self, a) {
+            (Prog::Taproot(tk, _), _) => return Some(Address::p2tr_tweaked(*tk, net)),
+            (Prog::Hash(h), Addr::SegWit) => Payload::WitnessProgram(WitnessProgram::new(WitnessVersion::V0, h.to_vec()).ok()?),
+            (Prog::Hash(h), Addr::P2SH) => Payload::ScriptHash(ScriptHash::from_byte_array(*h)),
+            (Prog::Hash(h), _) => Payload::PubkeyHash(PubkeyHash::from_byte_array(*h)),
+        };
+        Some(Address::new(net, payload))
+    }
+}
+
+/// 偏移量 i 对应的标量
+fn offset(i: usize) -> Scalar {
+    let mut b = [0u8; 32];
+    b[24..].copy_from_slice(&(i as u64).to_be_bytes());
+    Scalar::from_be_bytes(b).unwrap()
+}
+
+/// 还原候选私钥: (k + i) · λ^j，必要时取负
+fn recover(k: &SecretKey, i: usize, j: usize, neg: bool) -> SecretKey {
+    let lambda = Scalar::from_be_bytes(LAMBDA).unwrap();
+    let mut sk = k.add_tweak(&offset(i)).expect("k + i 不为零");
+    for _ in 0..j { sk = sk.mul_tweak(&lambda).expect("λ 非零"); }
+    if neg { sk.negate() } else { sk }
+}
+
+fn worker(shared: Arc<Shared>, tx: Sender<Found>, id: usize) {
+    let (settings, stop, cnt) = (&shared.settings, &shared.stop, &shared.cnt);
+    let secp = Secp256k1::new();
+    let net = settings.network;
+    let path = DerivationPath::from_str(deriv(settings.addr_type, net)).unwrap();
+    let skip = pfx_len(settings.addr_type, net);
+    let mut buf = String::with_capacity(64);
+    let mut local = 0u64;
+
+    // 根据设置选择 RNG
+    let mut secure_rng = OsRng;
+    let mut fast_rng = Xoshiro256PlusPlus::from_entropy();
+
+    // WIF-only 模式: 直接生成随机私钥，跳过 BIP39/BIP32
+    let wif_only = settings.output == Out::Wif;
+
+    // 增量模式: 每批一个随机起点 k，依次点加得到 k+1, k+2, …，再用自同态扩展候选
+    let mut walker = (wif_only && settings.keygen == KeyGen::Incremental)
+        .then(|| Walker::new(&secp, settings.batch_size as usize));
+    let mut points = Vec::new();
+    let negs: &[bool] = if settings.addr_type == Addr::Taproot { &[false] } else { &[false, true] };
+    let mut set = shared.set();
+
+    loop {
+        if stop.load(Ordering::Relaxed) { break; }
+        // 目标被替换时在批次之间换用新的匹配器
+        if shared.gen.load(Ordering::Relaxed) != set.gen { set = shared.set(); }
+        if shared.held(id) {
+            // 等待前先交上计数，暂停时显示的次数是准确的
+            cnt.fetch_add(local, Ordering::Relaxed);
+            local = 0;
+            if !shared.wait(id) { break; }
+        }
+
+        if let Some(w) = walker.as_mut() {
+            let mut key_bytes = [0u8; 32];
+            match settings.rng_mode {
+                RngMode::Secure => secure_rng.fill_bytes(&mut key_bytes),
+                RngMode::Fast => fast_rng.fill_bytes(&mut key_bytes),
+            }
+            let k = match SecretKey::from_slice(&key_bytes) { Ok(k) => k, Err(_) => continue };
+            if !w.batch(&secp, &k, &mut points) { continue; }
+
+            // 每个点取 P, λP, λ²P 及其负点共 6 个候选；Taproot 只看 x，负点地址相同只取 3 个
+            for (i, p) in points.iter().enumerate() {
+                for (j, x) in p.endo_x().iter().enumerate() {
+                    for &neg in negs {
+                        let pk = compress(x, p.y.is_odd() ^ neg);
+                        local += 1;
+
+                        if let Some(p) = shared.probe(&set, &secp, &pk, &mut buf, skip) {
+                            cnt.fetch_add(local, Ordering::Relaxed);
+                            local = 0;
+                            shared.report(&set, &secp, p, recover(&k, i, j, neg), None, &tx);
+                        }
+                    }
+                }
+            }
+
+            cnt.fetch_add(local, Ordering::Relaxed);
+            local = 0;
+            shared.over();
+            continue;
+        }
+
+        for _ in 0..settings.batch_size {
+            // 助记词模式一批可能要好几秒，每次派生都交上计数并检查，进度、上限和账本不会滞后；
+            // 私钥模式一批很快，只在批次之间检查
+            if !wif_only {
+                cnt.fetch_add(local, Ordering::Relaxed);
+                local = 0;
+                if shared.held(id) { break; }
+            }
+            let (sk, mn) = if wif_only {
+                // 快速模式: 直接生成随机私钥
+                let mut key_bytes = [0u8; 32];
+                match settings.rng_mode {
+                    RngMode::Secure => secure_rng.fill_bytes(&mut key_bytes),
+                    RngMode::Fast => fast_rng.fill_bytes(&mut key_bytes),
+                }
+
+                match SecretKey::from_slice(&key_bytes) {
+                    Ok(k) => (k, None),
+                    Err(_) => continue,
+                }
+            } else {
+                // 标准模式: BIP39 助记词 -> BIP32 派生
+                let mut ent = [0u8; 32];
+                match settings.rng_mode {
+                    RngMode::Secure => secure_rng.fill_bytes(&mut ent),
+                    RngMode::Fast => fast_rng.fill_bytes(&mut ent),
+                }
+
+                let mn = match Mnemonic::from_entropy(&ent) { Ok(m) => m, Err(_) => continue };
+                let seed = mn.to_seed("");
+                let root = match ExtendedPrivKey::new_master(net, &seed) { Ok(r) => r, Err(_) => continue };
+                let child = match root.derive_priv(&secp, &path) { Ok(k) => k, Err(_) => continue };
+                (child.private_key, Some(mn))
+            };
+
+            let pk = sk.public_key(&secp).serialize();
+            local += 1;
+
+            if let Some(p) = shared.probe(&set, &secp, &pk, &mut buf, skip) {
+                cnt.fetch_add(local, Ordering::Relaxed);
+                local = 0;
+                shared.report(&set, &secp, p, sk, mn.map(|m| m.to_string()), &tx);
+            }
+        }
+
+        if local >= 1000 {
+            cnt.fetch_add(local, Ordering::Relaxed);
+            local = 0;
+            shared.over();
+        }
+    }
+    cnt.fetch_add(local, Ordering::Relaxed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::secp256k1::PublicKey;
+
+    /// 点加器给出的每个候选（自同态与负点）还原出的私钥都应派生出同一个公钥
+    #[test]
+    fn recover_matches_candidates() {
+        let secp = Secp256k1::new();
+        let mut rng = rand::thread_rng();
+        let mut w = Walker::new(&secp, 16);
+        let mut points = vec![];
+        for _ in 0..10 {
+            let k = SecretKey::new(&mut rng);
+            assert!(w.batch(&secp, &k, &mut points));
+            for (i, p) in points.iter().enumerate() {
+                for (j, x) in p.endo_x().iter().enumerate() {
+                    for neg in [false, true] {
+                        let pk = compress(x, p.y.is_odd() ^ neg);
+                        let sk = recover(&k, i, j, neg);
+                        assert_eq!(PublicKey::from_secret_key(&secp, &sk).serialize(), pk, "i={} j={} neg={}", i, j, neg);
+                    }
+                }
+            }
+        }
+    }
+
+    /// 暂停的时间不算进时长上限: 暂停超过上限后继续，仍要再跑到上限才停
+    #[test]
+    fn pause_extends_deadline() {
+        let h = Searcher::new().threads(1).output(Out::Wif).target("qqqqqqqqqq")
+            .max_time(Duration::from_millis(300)).spawn().unwrap();
+        h.set_paused(true);
+        thread::sleep(Duration::from_millis(500));
+        h.set_paused(false);
+        thread::sleep(Duration::from_millis(100));
+        assert!(!h.is_stopped());
+        while !h.is_stopped() { thread::sleep(Duration::from_millis(20)); }
+        assert_eq!(h.limit(), Some(Limit::Time));
+        assert!(h.elapsed() >= Duration::from_millis(300), "{:?}", h.elapsed());
+        h.join();
+    }
+}